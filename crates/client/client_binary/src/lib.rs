@@ -66,6 +66,9 @@ fn new_game(
         connection_density: core_library::game_meta::ConnectionDensity::Dense,
         ticks_per_tick: 1,
         simulation_tick_amount: 1,
+        seed: None,
+        custom_map: None,
+        map_symmetry: core_library::game_meta::MapSymmetry::None,
     };
 
     let addr = game_server_info.http_url();
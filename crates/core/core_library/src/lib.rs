@@ -1,7 +1,8 @@
 pub use general::{
     actions, async_runners, auth_server, authentication, clone_async_sender, create_async_channel,
-    game_meta, game_simulation, network, objects, player, AsyncChannel, AsyncChannelReceiver,
-    AsyncChannelSender, PendingDatabaseData, TaskPoolRes,
+    game_meta, game_simulation, insert_buffered_async_sender, network, objects, player,
+    AsyncChannel, AsyncChannelReceiver, AsyncChannelSender, GameWorldChannels, PendingDatabaseData,
+    TaskPoolRes,
 };
 #[cfg(feature = "http_server_feature")]
 pub use http_server;
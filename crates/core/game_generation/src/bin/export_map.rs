@@ -0,0 +1,131 @@
+//! Renders a map to an SVG without needing a running server or client. The map is either generated from a [`NewGameSettings`]
+//! json file, or loaded from a game server database so that reported games can be inspected at any tick.
+//!
+//! `cargo run -p game_generation --bin export_map -- --settings settings.json --players 4 --output map.svg`
+//!
+//! `cargo run -p game_generation --bin export_map -- --database game_server_database.db --game <game id> --tick 120 --output map.svg`
+
+use std::{
+    path::{Path, PathBuf},
+    sync::mpsc,
+};
+
+use bevy::{ecs::world::World, utils::Uuid};
+use clap::Parser;
+use game_generation::{
+    allocate_players, custom_map::load_custom_map, generate_map_layout, insert_new_game_state,
+    map_export::render_svg, random_seed, StartSlots,
+};
+use general::{
+    auth_server::AccountId,
+    game_meta::{GameId, GamePlayers, GameSeed, NewGameSettings},
+    objects::ObjectIdService,
+    AsyncChannelSender,
+};
+use rusqlite::{Connection, OpenFlags};
+use sqlite_database::{
+    loading::{load_game_objects, saved_game},
+    schemes::game_server::game_tables::InsertGameCurvesRow,
+};
+
+#[derive(Parser)]
+struct ExportMapArgs {
+    /// Path to a json file containing the [`NewGameSettings`] to generate the map with
+    #[arg(
+        long,
+        required_unless_present = "database",
+        conflicts_with = "database"
+    )]
+    settings: Option<PathBuf>,
+    /// Overrides the seed in the settings file
+    #[arg(long, conflicts_with = "database")]
+    seed: Option<u64>,
+    /// How many players to give home outposts to
+    #[arg(long, default_value_t = 0, conflicts_with = "database")]
+    players: u8,
+    /// Path to a game server database to load a saved game from
+    #[arg(long, requires = "game")]
+    database: Option<PathBuf>,
+    /// The id of the saved game to load from the database
+    #[arg(long)]
+    game: Option<Uuid>,
+    /// The tick to render the map at
+    #[arg(long, default_value_t = 0)]
+    tick: u64,
+    /// Where to write the SVG
+    #[arg(short, long)]
+    output: PathBuf,
+}
+
+fn main() -> Result<(), String> {
+    let args = ExportMapArgs::parse();
+    let mut game_world = match (&args.database, args.game, &args.settings) {
+        (Some(database), Some(game_id), _) => {
+            load_saved_game_world(database, GameId { id: game_id })?
+        }
+        (_, _, Some(settings)) => generate_game_world(settings, args.seed, args.players)?,
+        _ => return Err("Either --settings or --database and --game are required".to_string()),
+    };
+
+    std::fs::write(&args.output, render_svg(&mut game_world, args.tick))
+        .map_err(|err| err.to_string())?;
+    println!("Exported map at tick {} to {:?}", args.tick, args.output);
+    Ok(())
+}
+
+/// Generates a new map from the settings file, giving home outposts to `players` players
+fn generate_game_world(settings: &Path, seed: Option<u64>, players: u8) -> Result<World, String> {
+    let settings = std::fs::read_to_string(settings).map_err(|err| err.to_string())?;
+    let settings: NewGameSettings =
+        serde_json::from_str(&settings).map_err(|err| err.to_string())?;
+
+    let seed = seed
+        .or(settings.seed)
+        .map(|seed| GameSeed { seed })
+        .unwrap_or_else(random_seed);
+    let layout = match &settings.custom_map {
+        Some(map_name) => load_custom_map(map_name, settings.max_player_count)?.map_layout(),
+        None => generate_map_layout(&settings, seed),
+    };
+
+    // Nothing is saved, the receiving half of the channel is dropped so inserted rows go nowhere
+    let (sender, _) = mpsc::channel::<InsertGameCurvesRow>();
+    let mut game_world = World::new();
+    game_world.insert_resource(ObjectIdService::new());
+    game_world.insert_resource(seed);
+    game_world.insert_resource(AsyncChannelSender {
+        sender_channel: sender,
+    });
+    insert_new_game_state(&mut game_world, &GameId { id: Uuid::nil() }, layout);
+
+    if players > 0 {
+        let players = GamePlayers {
+            players: (0..players)
+                .map(|player| AccountId {
+                    id: Uuid::from_u128(player as u128 + 1),
+                })
+                .collect(),
+        };
+        allocate_players(&mut game_world, &players)?;
+    }
+    println!("Generated map with seed {}", seed.seed);
+    Ok(game_world)
+}
+
+/// Loads every object of a saved game out of a game server database, exactly as the server would load it
+fn load_saved_game_world(database: &Path, game_id: GameId) -> Result<World, String> {
+    // Opened read only so that inspecting a live servers database never changes it
+    let connection = Connection::open_with_flags(database, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|err| err.to_string())?;
+    let Some(saved_game) = saved_game(&connection, &game_id).map_err(|err| err.to_string())? else {
+        return Err(format!("Game {} not found", game_id.id_as_string()));
+    };
+
+    let mut game_world = World::new();
+    load_game_objects(&connection, &mut game_world, &game_id).map_err(|err| err.to_string())?;
+    game_world.insert_resource(StartSlots {
+        outposts: saved_game.start_slots,
+    });
+    println!("Loaded game {}", game_id.id_as_string());
+    Ok(game_world)
+}
@@ -0,0 +1,154 @@
+//! Responsible for loading hand-authored maps from data files.
+//!
+//! Custom maps are JSON files stored in [`CUSTOM_MAP_DIRECTORY`] and are used instead of a procedurally generated map when
+//! [`NewGameSettings::custom_map`](general::game_meta::NewGameSettings) is set.
+
+use std::path::Path;
+
+use bevy::math::Vec2;
+use serde::{Deserialize, Serialize};
+
+use crate::{start_positions::graph_distances, MapLayout};
+
+/// The directory, relative to the server, that custom map files are loaded from
+pub const CUSTOM_MAP_DIRECTORY: &str = "maps";
+
+/// A hand-authored map.
+///
+/// Connections and start slots are indexes into `outposts`
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CustomMap {
+    /// The total physical size of the map. Every outpost must be inside `(0, 0)..map_size`, and the game is created with this
+    /// as its [`MapSize`](general::game_meta::MapSize) in place of the requested one
+    pub map_size: Vec2,
+    pub outposts: Vec<CustomMapOutpost>,
+    pub connections: Vec<(usize, usize)>,
+    /// Outposts that players can be given as their home when the game starts
+    pub start_slots: Vec<usize>,
+}
+
+/// A single outpost in a [`CustomMap`]
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CustomMapOutpost {
+    pub position: Vec2,
+}
+
+impl CustomMap {
+    /// Verifies that the map can be played with the given amount of players.
+    ///
+    /// Every outpost must be inside the map and reachable from every other outpost, and there must be a start slot for every player
+    pub fn validate(&self, max_player_count: u8) -> Result<(), String> {
+        if self.outposts.is_empty() {
+            return Err("Map has no outposts".to_string());
+        }
+        if self.map_size.x <= 0.0 || self.map_size.y <= 0.0 {
+            return Err("Map size must be positive".to_string());
+        }
+
+        for (index, outpost) in self.outposts.iter().enumerate() {
+            if outpost.position.x < 0.0
+                || outpost.position.y < 0.0
+                || outpost.position.x >= self.map_size.x
+                || outpost.position.y >= self.map_size.y
+            {
+                return Err(format!("Outpost {} is outside of the map", index));
+            }
+        }
+
+        for (a, b) in self.connections.iter() {
+            if *a >= self.outposts.len() || *b >= self.outposts.len() || a == b {
+                return Err(format!("Connection ({}, {}) is invalid", a, b));
+            }
+        }
+
+        let distances = graph_distances(self.outposts.len(), &self.connections);
+        if distances[0].contains(&usize::MAX) {
+            return Err("Not every outpost is connected".to_string());
+        }
+
+        let mut start_slots = self.start_slots.clone();
+        start_slots.sort();
+        start_slots.dedup();
+        if start_slots.len() != self.start_slots.len()
+            || start_slots.iter().any(|slot| *slot >= self.outposts.len())
+        {
+            return Err("Start slots are invalid".to_string());
+        }
+        if start_slots.len() < max_player_count as usize {
+            return Err(format!(
+                "Map has {} start slots but needs {}",
+                start_slots.len(),
+                max_player_count
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Converts the map into the [`MapLayout`] used to create the game
+    pub fn map_layout(&self) -> MapLayout {
+        MapLayout {
+            outposts: self
+                .outposts
+                .iter()
+                .map(|outpost| outpost.position)
+                .collect(),
+            connections: self.connections.clone(),
+            start_slots: self.start_slots.clone(),
+        }
+    }
+}
+
+/// Loads and validates the custom map with the given file name from [`CUSTOM_MAP_DIRECTORY`]
+pub fn load_custom_map(map_name: &str, max_player_count: u8) -> Result<CustomMap, String> {
+    // Map names come from players so they must not be able to leave the map directory
+    if map_name.is_empty() || map_name.contains(['/', '\\']) || map_name.contains("..") {
+        return Err("Invalid map name".to_string());
+    }
+
+    let path = Path::new(CUSTOM_MAP_DIRECTORY).join(format!("{}.json", map_name));
+    let file = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+    let map: CustomMap = serde_json::from_str(&file).map_err(|err| err.to_string())?;
+    map.validate(max_player_count)?;
+    Ok(map)
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::math::Vec2;
+
+    use crate::custom_map::{load_custom_map, CustomMap};
+
+    #[test]
+    fn test_custom_map_validation() {
+        let map: CustomMap = serde_json::from_str(
+            r#"{
+                "map_size": [100.0, 100.0],
+                "outposts": [
+                    { "position": [10.0, 10.0] },
+                    { "position": [50.0, 50.0] },
+                    { "position": [90.0, 90.0] }
+                ],
+                "connections": [[0, 1], [1, 2]],
+                "start_slots": [0, 2]
+            }"#,
+        )
+        .unwrap();
+        assert!(map.validate(2).is_ok());
+        assert!(map.validate(3).is_err());
+
+        let mut disconnected = map.clone();
+        disconnected.connections.pop();
+        assert!(disconnected.validate(2).is_err());
+
+        let mut out_of_bounds = map.clone();
+        out_of_bounds.outposts[1].position = Vec2::new(150.0, 50.0);
+        assert!(out_of_bounds.validate(2).is_err());
+
+        let mut no_size = map.clone();
+        no_size.map_size = Vec2::ZERO;
+        assert!(no_size.validate(2).is_err());
+
+        assert!(load_custom_map("../secrets", 2).is_err());
+    }
+}
@@ -1,41 +1,135 @@
-use bevy::{ecs::world::World, math::Vec2};
+use bevy::{
+    ecs::{query::With, system::Resource, world::World},
+    math::Vec2,
+};
 use bevy_state_curves::prelude::{CurveTrait, SteppedCurve};
 use general::{
-    game_meta::{GameId, NewGameSettings},
-    game_simulation::GameWorldSimulationSchedule,
+    game_meta::{GameId, GamePlayers, GameSeed, MapSymmetry, NewGameSettings},
+    game_simulation::{
+        economy::PlayerResources, event_scheduling::NextInterestingTick,
+        forecasts::ActionForecasts, visibility::PlayerVisibility, DueActions, FailedActions,
+        GameWorldSimulationSchedule, SimulationTicks,
+    },
     objects::{
-        core_components::{ObjectId, ObjectPosition},
+        core_components::{ObjectGeneral, ObjectId, ObjectPosition},
+        outpost::{Outpost, OutpostConnections, OutpostGarrison, OutpostType},
+        units::UnitComposition,
         ObjectIdService,
     },
     AsyncChannelSender,
 };
-
+use map_symmetry::generate_symmetric_layout;
+use outpost_connections::connect_outposts;
+use outpost_placement::place_outposts;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
 use sqlite_database::{
-    game_world_setup_saving, saving::SaveSchedule,
+    game_world_setup_saving,
+    saving::{ExistsInDatabase, SaveSchedule},
     schemes::game_server::game_tables::InsertGameCurvesRow,
 };
+use start_positions::allocate_start_positions;
+
+pub mod custom_map;
+pub mod map_export;
+pub mod map_symmetry;
+pub mod outpost_connections;
+pub mod outpost_placement;
+pub mod start_positions;
+
+/// The units in every neutral outpost when a map is generated
+pub const NEUTRAL_GARRISON: UnitComposition = UnitComposition {
+    infantry: 5,
+    cavalry: 0,
+    artillery: 0,
+};
+/// The units in every players home outpost when the game starts
+pub const HOME_GARRISON: UnitComposition = UnitComposition {
+    infantry: 12,
+    cavalry: 5,
+    artillery: 3,
+};
+/// How likely each [`OutpostType`] is to be picked for a generated outpost, relative to the other types
+pub const OUTPOST_TYPE_WEIGHTS: [(OutpostType, u32); 3] = [
+    (OutpostType::Fortress, 2),
+    (OutpostType::Depot, 5),
+    (OutpostType::Factory, 3),
+];
 
 /// Function responsible for generating the new game world with the correct default setup. This is used for both loading old games and starting new games
 pub fn create_game_world(
     server_world: &mut World,
     game_id: &GameId,
-    _settings: &NewGameSettings,
+    settings: &NewGameSettings,
     id_service: &mut ObjectIdService,
+    seed: GameSeed,
 ) -> World {
     let mut game_world = World::new();
     game_world.insert_resource(id_service.clone());
     game_world.insert_resource(*game_id);
+    game_world.insert_resource(seed);
+    game_world.insert_resource(settings.clone());
+    game_world.init_resource::<SimulationTicks>();
+    game_world.init_resource::<DueActions>();
+    game_world.init_resource::<FailedActions>();
+    game_world.init_resource::<ActionForecasts>();
+    game_world.init_resource::<PlayerVisibility>();
+    game_world.init_resource::<NextInterestingTick>();
     game_world.add_schedule(SaveSchedule::new_schedule());
     game_world.add_schedule(GameWorldSimulationSchedule::new_schedule());
     game_world_setup_saving(server_world, &mut game_world);
     game_world
 }
 
-pub fn insert_new_game_state(
-    game_world: &mut World,
-    new_game_id: &GameId,
-    settings: &NewGameSettings,
-) {
+/// The layout of a map. Connections and start slots are indexes into `outposts`
+#[derive(Clone, PartialEq, Debug)]
+pub struct MapLayout {
+    pub outposts: Vec<Vec2>,
+    pub connections: Vec<(usize, usize)>,
+    /// Outposts that players can be given as their home. Empty if any outpost can be a home
+    pub start_slots: Vec<usize>,
+}
+
+/// The outposts in a game world that players can be given as their home. Empty if any outpost can be a home
+#[derive(Resource, Clone, Default)]
+pub struct StartSlots {
+    pub outposts: Vec<ObjectId>,
+}
+
+/// Generates the layout of a new map. The same settings and seed will always generate the same layout
+pub fn generate_map_layout(settings: &NewGameSettings, seed: GameSeed) -> MapLayout {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed.seed);
+    if settings.map_symmetry != MapSymmetry::None {
+        return generate_symmetric_layout(
+            settings.map_size.map_size(),
+            *settings.map_point_count.map_point_count(),
+            &settings.connection_density,
+            settings.map_symmetry,
+            settings.max_player_count,
+            &mut rng,
+        );
+    }
+    let outposts = place_outposts(
+        settings.map_size.map_size(),
+        *settings.map_point_count.map_point_count(),
+        &mut rng,
+    );
+    let connections = connect_outposts(&outposts, &settings.connection_density);
+    MapLayout {
+        outposts,
+        connections,
+        start_slots: vec![],
+    }
+}
+
+/// Returns a new random seed, used for games that were not given a seed in their [`NewGameSettings`]
+pub fn random_seed() -> GameSeed {
+    GameSeed {
+        seed: rand::random(),
+    }
+}
+
+pub fn insert_new_game_state(game_world: &mut World, new_game_id: &GameId, layout: MapLayout) {
     let insert_game_curves_row = game_world
         .remove_resource::<AsyncChannelSender<InsertGameCurvesRow>>()
         .expect("Update Row resource should be in main world");
@@ -43,26 +137,204 @@ pub fn insert_new_game_state(
     let mut id_service = game_world.remove_resource::<ObjectIdService>().expect(
         "ObjectIdService must be inserted into game world prior to updating any game_world_state",
     );
+    // Outpost types are picked from the games seed so the same map always gets the same types
+    let mut rng = ChaCha8Rng::seed_from_u64(
+        game_world
+            .get_resource::<GameSeed>()
+            .map(|seed| seed.seed)
+            .unwrap_or_default(),
+    );
+
+    let outpost_ids: Vec<ObjectId> = layout
+        .outposts
+        .iter()
+        .map(|_| id_service.new_object_id())
+        .collect();
+
+    let mut outpost_connections = vec![OutpostConnections::default(); outpost_ids.len()];
+    for (a, b) in layout.connections {
+        outpost_connections[a].connections.push(outpost_ids[b]);
+        outpost_connections[b].connections.push(outpost_ids[a]);
+    }
+    game_world.insert_resource(StartSlots {
+        outposts: layout
+            .start_slots
+            .iter()
+            .map(|slot| outpost_ids[*slot])
+            .collect(),
+    });
 
-    for i in 0..*settings.map_point_count.map_point_count() {
+    for ((position, id), connections) in layout
+        .outposts
+        .into_iter()
+        .zip(outpost_ids)
+        .zip(outpost_connections)
+    {
         let mut pos = SteppedCurve::<ObjectPosition>::new();
+        pos.insert_keyframe(0, ObjectPosition { position });
+
+        let mut connections_curve = SteppedCurve::<OutpostConnections>::new();
+        connections_curve.insert_keyframe(0, connections);
 
-        pos.insert_keyframe(
+        // Every outpost starts neutral, players are given their homes when the game starts
+        let mut general = SteppedCurve::<ObjectGeneral>::new();
+        general.insert_keyframe(0, ObjectGeneral::default());
+
+        let mut garrison = SteppedCurve::<OutpostGarrison>::new();
+        garrison.insert_keyframe(
             0,
-            ObjectPosition {
-                position: Vec2::new(i as f32, i as f32),
+            OutpostGarrison {
+                units: NEUTRAL_GARRISON,
             },
         );
 
-        let id = id_service.new_object_id();
-        let Some(row) = InsertGameCurvesRow::new_row(*new_game_id, &id, None, &pos) else {
+        let mut outpost_type = SteppedCurve::<OutpostType>::new();
+        outpost_type.insert_keyframe(0, random_outpost_type(&mut rng));
+
+        let Some(row) = InsertGameCurvesRow::new_row(*new_game_id, &id, &general, &pos)
+            .and_then(|row| row.with_data(&connections_curve))
+            .and_then(|row| row.with_data(&garrison))
+            .and_then(|row| row.with_data(&outpost_type))
+        else {
             continue;
         };
         let _ = insert_game_curves_row.sender_channel.send(row);
 
-        game_world.spawn((pos, ObjectId::new(id.id)));
+        game_world.spawn((
+            pos,
+            connections_curve,
+            general,
+            garrison,
+            outpost_type,
+            id,
+            Outpost,
+            ExistsInDatabase,
+        ));
     }
 
     game_world.insert_resource(id_service);
     game_world.insert_resource(insert_game_curves_row);
 }
+
+/// Picks a random [`OutpostType`] using [`OUTPOST_TYPE_WEIGHTS`]
+fn random_outpost_type(rng: &mut impl Rng) -> OutpostType {
+    let total: u32 = OUTPOST_TYPE_WEIGHTS.iter().map(|(_, weight)| weight).sum();
+    let mut roll = rng.gen_range(0..total);
+    for (outpost_type, weight) in OUTPOST_TYPE_WEIGHTS {
+        if roll < weight {
+            return outpost_type;
+        }
+        roll -= weight;
+    }
+    OutpostType::default()
+}
+
+/// Gives every player in [`GamePlayers`] a home outpost, recording them as its owner at tick 0 with a [`HOME_GARRISON`]. Homes
+/// are always an [`OutpostType::Factory`], and every player is spawned as an entity holding their [`PlayerResources`].
+///
+/// Homes are picked by [`allocate_start_positions`] from the games [`StartSlots`]. Fails if the map does not have enough outposts for every player
+pub fn allocate_players(game_world: &mut World, players: &GamePlayers) -> Result<(), String> {
+    let mut outposts: Vec<(ObjectId, Vec<ObjectId>)> = game_world
+        .query_filtered::<(&ObjectId, &SteppedCurve<OutpostConnections>), With<Outpost>>()
+        .iter(game_world)
+        .map(|(id, connections)| {
+            let connections = connections
+                .get_state(0)
+                .map(|state| state.connections.clone())
+                .unwrap_or_default();
+            (*id, connections)
+        })
+        .collect();
+    // Sorted so that the same map always gives the same homes
+    outposts.sort_by_key(|(id, _)| *id);
+
+    let index_of = |id: &ObjectId| outposts.iter().position(|(other, _)| other == id);
+    let mut connections = vec![];
+    for (index, (_, outpost_connections)) in outposts.iter().enumerate() {
+        for other in outpost_connections.iter().filter_map(index_of) {
+            if index < other {
+                connections.push((index, other));
+            }
+        }
+    }
+    let start_slots = game_world
+        .get_resource::<StartSlots>()
+        .cloned()
+        .unwrap_or_default();
+    let candidates: Vec<usize> = if start_slots.outposts.is_empty() {
+        (0..outposts.len()).collect()
+    } else {
+        start_slots.outposts.iter().filter_map(index_of).collect()
+    };
+
+    let Some(homes) = allocate_start_positions(
+        outposts.len(),
+        &connections,
+        &candidates,
+        players.players.len(),
+    ) else {
+        return Err("Not enough outposts for every player".to_string());
+    };
+
+    let home_ids: Vec<(ObjectId, ObjectGeneral)> = homes
+        .into_iter()
+        .zip(players.players.iter())
+        .map(|(home, player)| (outposts[home].0, ObjectGeneral::new(player.clone())))
+        .collect();
+
+    let mut query = game_world.query::<(
+        &ObjectId,
+        &mut SteppedCurve<ObjectGeneral>,
+        &mut SteppedCurve<OutpostGarrison>,
+        &mut SteppedCurve<OutpostType>,
+    )>();
+    for (id, mut general, mut garrison, mut outpost_type) in query.iter_mut(game_world) {
+        if let Some((_, owner)) = home_ids.iter().find(|(home, _)| home == id) {
+            general.insert_keyframe(0, owner.clone());
+            garrison.insert_keyframe(
+                0,
+                OutpostGarrison {
+                    units: HOME_GARRISON,
+                },
+            );
+            outpost_type.insert_keyframe(0, OutpostType::Factory);
+        }
+    }
+
+    for player in players.players.iter() {
+        let mut resources = SteppedCurve::<PlayerResources>::new();
+        resources.insert_keyframe(0, PlayerResources::default());
+        game_world.spawn((player.clone(), resources));
+    }
+
+    game_world.insert_resource(players.clone());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use general::game_meta::{
+        ConnectionDensity, GameSeed, MapPointCount, MapSize, MapSymmetry, NewGameSettings,
+    };
+
+    use crate::generate_map_layout;
+
+    #[test]
+    fn test_seeded_map_generation() {
+        let settings = NewGameSettings {
+            max_player_count: 4,
+            map_point_count: MapPointCount::Normal,
+            map_size: MapSize::Medium,
+            connection_density: ConnectionDensity::Sparse,
+            ticks_per_tick: 1,
+            simulation_tick_amount: 1,
+            seed: None,
+            custom_map: None,
+            map_symmetry: MapSymmetry::None,
+        };
+
+        let layout = generate_map_layout(&settings, GameSeed { seed: 1 });
+        assert_eq!(layout, generate_map_layout(&settings, GameSeed { seed: 1 }));
+        assert_ne!(layout, generate_map_layout(&settings, GameSeed { seed: 2 }));
+    }
+}
@@ -0,0 +1,226 @@
+//! Responsible for rendering game worlds to SVG so that maps can be inspected without launching the client.
+//!
+//! Renders every outpost at its [`ObjectPosition`] at the requested tick, the connections between them, who owns them, their
+//! [`OutpostType`], and which outposts are start slots.
+
+use std::fmt::Write;
+
+use bevy::{
+    ecs::{query::With, world::World},
+    math::Vec2,
+    utils::HashMap,
+};
+use bevy_state_curves::prelude::{CurveTrait, SteppedCurve};
+use general::{
+    auth_server::AccountId,
+    objects::{
+        core_components::{ObjectGeneral, ObjectId, ObjectPosition},
+        outpost::{Outpost, OutpostConnections, OutpostType},
+    },
+};
+
+use crate::StartSlots;
+
+/// Empty space left around the outermost outposts
+const MARGIN: f32 = 20.0;
+const OUTPOST_RADIUS: f32 = 4.0;
+const NEUTRAL_COLOR: &str = "#9e9e9e";
+/// Colors given to players in the order that they are first found
+const PLAYER_COLORS: [&str; 8] = [
+    "#e53935", "#1e88e5", "#43a047", "#fdd835", "#8e24aa", "#fb8c00", "#00acc1", "#d81b60",
+];
+
+struct RenderedOutpost {
+    id: ObjectId,
+    position: Vec2,
+    connections: Vec<ObjectId>,
+    owner: Option<AccountId>,
+    /// The name of the outposts [`OutpostType`]
+    outpost_type: String,
+}
+
+/// Renders every outpost in the game world as it is at `tick` into an SVG document
+pub fn render_svg(game_world: &mut World, tick: u64) -> String {
+    let mut outposts: Vec<RenderedOutpost> = game_world
+        .query_filtered::<(
+            &ObjectId,
+            &SteppedCurve<ObjectPosition>,
+            Option<&SteppedCurve<OutpostConnections>>,
+            Option<&SteppedCurve<ObjectGeneral>>,
+            Option<&SteppedCurve<OutpostType>>,
+        ), With<Outpost>>()
+        .iter(game_world)
+        .filter_map(|(id, position, connections, general, outpost_type)| {
+            let position = position.get_state(tick)?.position;
+            Some(RenderedOutpost {
+                id: *id,
+                position,
+                connections: connections
+                    .and_then(|connections| connections.get_state(tick))
+                    .map(|state| state.connections.clone())
+                    .unwrap_or_default(),
+                owner: general
+                    .and_then(|general| general.get_state(tick))
+                    .and_then(|state| state.general().cloned()),
+                outpost_type: outpost_type
+                    .and_then(|outpost_type| outpost_type.get_state(tick))
+                    .map(|state| format!("{:?}", state))
+                    .unwrap_or_default(),
+            })
+        })
+        .collect();
+    outposts.sort_by_key(|outpost| outpost.id);
+
+    let start_slots = game_world
+        .get_resource::<StartSlots>()
+        .cloned()
+        .unwrap_or_default();
+
+    let (mut min, mut max) = (Vec2::ZERO, Vec2::ZERO);
+    for outpost in outposts.iter() {
+        min = min.min(outpost.position);
+        max = max.max(outpost.position);
+    }
+    let min = min - MARGIN;
+    let size = max + MARGIN - min;
+
+    let positions: HashMap<ObjectId, Vec2> = outposts
+        .iter()
+        .map(|outpost| (outpost.id, outpost.position))
+        .collect();
+    let mut player_colors: HashMap<AccountId, &str> = HashMap::new();
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}" width="{}" height="{}">"#,
+        min.x, min.y, size.x, size.y, size.x, size.y
+    );
+    let _ = writeln!(
+        svg,
+        r##"<rect x="{}" y="{}" width="{}" height="{}" fill="#212121"/>"##,
+        min.x, min.y, size.x, size.y
+    );
+
+    for outpost in outposts.iter() {
+        for other in outpost.connections.iter() {
+            // Connections are stored on both outposts so only draw them once
+            if outpost.id > *other {
+                continue;
+            }
+            let Some(other_position) = positions.get(other) else {
+                continue;
+            };
+            let _ = writeln!(
+                svg,
+                r##"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="#616161" stroke-width="1"/>"##,
+                outpost.position.x, outpost.position.y, other_position.x, other_position.y
+            );
+        }
+    }
+
+    for outpost in outposts.iter() {
+        let color = match &outpost.owner {
+            Some(owner) => {
+                let next_color = PLAYER_COLORS[player_colors.len() % PLAYER_COLORS.len()];
+                *player_colors.entry(owner.clone()).or_insert(next_color)
+            }
+            None => NEUTRAL_COLOR,
+        };
+        if start_slots.outposts.contains(&outpost.id) {
+            let _ = writeln!(
+                svg,
+                r#"<circle cx="{}" cy="{}" r="{}" fill="none" stroke="white" stroke-width="1"/>"#,
+                outpost.position.x,
+                outpost.position.y,
+                OUTPOST_RADIUS * 2.0
+            );
+        }
+        let _ = writeln!(
+            svg,
+            r#"<circle cx="{}" cy="{}" r="{}" fill="{}"><title>{} {}</title></circle>"#,
+            outpost.position.x,
+            outpost.position.y,
+            OUTPOST_RADIUS,
+            color,
+            outpost.id.id,
+            outpost.outpost_type
+        );
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{ecs::world::World, math::Vec2, utils::Uuid};
+    use bevy_state_curves::prelude::{CurveTrait, SteppedCurve};
+    use general::{
+        auth_server::AccountId,
+        objects::{
+            core_components::{ObjectGeneral, ObjectId, ObjectPosition},
+            outpost::{Outpost, OutpostConnections, OutpostType},
+        },
+    };
+
+    use crate::map_export::{render_svg, NEUTRAL_COLOR, PLAYER_COLORS};
+
+    fn spawn_outpost(game_world: &mut World, id: u32, position: Vec2, connection: u32) {
+        let mut position_curve = SteppedCurve::new();
+        position_curve.insert_keyframe(0, ObjectPosition { position });
+        let mut connections = SteppedCurve::new();
+        connections.insert_keyframe(
+            0,
+            OutpostConnections {
+                connections: vec![ObjectId::new(connection)],
+            },
+        );
+        let mut outpost_type = SteppedCurve::new();
+        outpost_type.insert_keyframe(0, OutpostType::Fortress);
+        game_world.spawn((
+            ObjectId::new(id),
+            Outpost,
+            position_curve,
+            connections,
+            outpost_type,
+        ));
+    }
+
+    #[test]
+    fn test_render_svg() {
+        let mut game_world = World::new();
+        spawn_outpost(&mut game_world, 0, Vec2::new(10.0, 10.0), 1);
+        spawn_outpost(&mut game_world, 1, Vec2::new(50.0, 10.0), 0);
+
+        // The second outpost is captured on tick 5
+        let mut general = SteppedCurve::new();
+        general.insert_keyframe(0, ObjectGeneral::default());
+        general.insert_keyframe(
+            5,
+            ObjectGeneral::new(AccountId {
+                id: Uuid::from_u128(1),
+            }),
+        );
+        let outpost = game_world
+            .query::<(bevy::ecs::entity::Entity, &ObjectId)>()
+            .iter(&game_world)
+            .find(|(_, id)| id.id == 1)
+            .map(|(entity, _)| entity)
+            .unwrap();
+        game_world.entity_mut(outpost).insert(general);
+
+        let before_capture = render_svg(&mut game_world, 0);
+        assert!(before_capture.starts_with("<svg"));
+        assert!(before_capture.trim_end().ends_with("</svg>"));
+        // The connection is stored on both outposts but drawn once
+        assert_eq!(before_capture.matches("<line").count(), 1);
+        assert_eq!(before_capture.matches("<circle").count(), 2);
+        assert_eq!(before_capture.matches(NEUTRAL_COLOR).count(), 2);
+        assert!(before_capture.contains("Fortress"));
+
+        let after_capture = render_svg(&mut game_world, 5);
+        assert_eq!(after_capture.matches(NEUTRAL_COLOR).count(), 1);
+        assert!(after_capture.contains(PLAYER_COLORS[0]));
+    }
+}
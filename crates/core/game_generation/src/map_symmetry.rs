@@ -0,0 +1,368 @@
+//! Responsible for generating maps that are fair by construction.
+//!
+//! A single slice of the map is generated inside the largest circle that fits on the map and then copied around the center
+//! once per player, so every player gets the same geometry. With [`MapSymmetry::Mirrored`] only half of a slice is generated
+//! and it is mirrored to create the full slice.
+//!
+//! Maps are generated when the lobby is created, before it is known how many players will join, so there is one slice for
+//! every player the game allows, [`NewGameSettings::max_player_count`](general::game_meta::NewGameSettings). A game that
+//! starts with fewer players keeps every slice, and [`allocate_players`](crate::allocate_players) spreads the players over
+//! the start slots as evenly as it can. Only full games are perfectly symmetric.
+
+use std::{collections::HashSet, f32::consts::TAU};
+
+use bevy::math::Vec2;
+use general::game_meta::{ConnectionDensity, MapSymmetry};
+use rand::Rng;
+
+use crate::{
+    outpost_connections::{connections_cross, delaunay_edges, DisjointSets},
+    outpost_placement::{minimum_outpost_spacing, place_outposts_within},
+    MapLayout,
+};
+
+/// How close the copy of an outpost must be to an outpost to be considered the same outpost
+const MATCH_DISTANCE: f32 = 0.01;
+
+/// Generates a map where every player gets the same geometry and a start slot in the same place of their slice.
+///
+/// The amount of outposts is rounded so that every copy of the slice has the same amount. Uses `player_count` slices
+pub fn generate_symmetric_layout(
+    map_size: Vec2,
+    outpost_count: u16,
+    density: &ConnectionDensity,
+    symmetry: MapSymmetry,
+    player_count: u8,
+    rng: &mut impl Rng,
+) -> MapLayout {
+    let player_count = player_count.max(1) as usize;
+    let transforms = symmetry_transforms(map_size, symmetry, player_count);
+    let copies = transforms.len();
+
+    let center = map_size / 2.0;
+    let radius = map_size.min_element() / 2.0;
+    let slice_angle = TAU / copies as f32;
+    let spacing = minimum_outpost_spacing(map_size, outpost_count);
+    let slice_count = (outpost_count as usize).div_ceil(copies).max(1) as u16;
+
+    // Outposts are kept half the spacing away from the edges of the slice so that their copies are still far enough away
+    let inside = |point: Vec2, spacing: f32| {
+        let offset = point - center;
+        let distance = offset.length();
+        if distance > radius - spacing / 2.0 || distance < spacing / 2.0 {
+            return false;
+        }
+        if copies == 1 {
+            return true;
+        }
+        let angle = offset.y.atan2(offset.x).rem_euclid(TAU);
+        if angle >= slice_angle {
+            return false;
+        }
+        let edge_angle = angle.min(slice_angle - angle);
+        let edge_distance = if edge_angle < std::f32::consts::FRAC_PI_2 {
+            distance * edge_angle.sin()
+        } else {
+            distance
+        };
+        edge_distance >= spacing / 2.0
+    };
+    let slice = place_outposts_within(map_size, slice_count, spacing, inside, rng);
+
+    // Every copy of the slice is stored one after another, the first copy is the slice itself
+    let outposts: Vec<Vec2> = transforms
+        .iter()
+        .flat_map(|transform| slice.iter().map(|point| transform(*point)))
+        .collect();
+
+    let connections = symmetric_connections(&outposts, &transforms, density);
+
+    // The outpost furthest from the center in every rotated copy is a start slot
+    let start_slots = slice
+        .iter()
+        .enumerate()
+        .max_by(|(_, a), (_, b)| {
+            a.distance_squared(center)
+                .total_cmp(&b.distance_squared(center))
+        })
+        .map(|(index, _)| {
+            (0..player_count)
+                .map(|player| player * slice.len() * (copies / player_count) + index)
+                .collect()
+        })
+        .unwrap_or_default();
+
+    MapLayout {
+        outposts,
+        connections,
+        start_slots,
+    }
+}
+
+/// Connects the outposts the same way [`connect_outposts`](crate::outpost_connections::connect_outposts) does, except
+/// connections are always added together with every copy of them so that every slice is connected identically.
+///
+/// Mirrored outposts are often equally valid ways to triangulate the map, so copies of a connection that would cross
+/// existing connections are left out entirely. Outposts that are left disconnected by this are connected afterwards
+fn symmetric_connections(
+    outposts: &[Vec2],
+    transforms: &[Box<dyn Fn(Vec2) -> Vec2>],
+    density: &ConnectionDensity,
+) -> Vec<(usize, usize)> {
+    if outposts.len() < 2 {
+        return vec![];
+    }
+
+    // The outpost each outpost is moved onto by each transform
+    let copies_of: Vec<Vec<Option<usize>>> = transforms
+        .iter()
+        .map(|transform| {
+            outposts
+                .iter()
+                .map(|outpost| find_outpost(outposts, transform(*outpost)))
+                .collect()
+        })
+        .collect();
+
+    let length = |edge: &(usize, usize)| outposts[edge.0].distance_squared(outposts[edge.1]);
+    let mut candidate_edges = delaunay_edges(outposts);
+    candidate_edges.sort_by(|a, b| length(a).total_cmp(&length(b)));
+
+    let groups = edge_groups(outposts, &copies_of, candidate_edges.iter().copied());
+
+    let crosses = |connections: &[(usize, usize)], group: &[(usize, usize)]| {
+        group.iter().any(|edge| {
+            connections
+                .iter()
+                .any(|existing| connections_cross(outposts, *edge, *existing))
+        })
+    };
+
+    // Kruskal's algorithm over whole groups of copies
+    let mut sets = DisjointSets::new(outposts.len());
+    let mut components = outposts.len();
+    let mut connections = vec![];
+    let mut extra_groups = vec![];
+    for group in groups {
+        if crosses(&connections, &group) {
+            continue;
+        }
+        if group.iter().any(|(a, b)| sets.find(*a) != sets.find(*b)) {
+            for (a, b) in group.iter() {
+                if sets.union(*a, *b) {
+                    components -= 1;
+                }
+            }
+            connections.extend(group);
+        } else {
+            extra_groups.push(group);
+        }
+    }
+
+    // Leaving out groups that cross can leave outposts disconnected. Connect them with the shortest groups of copies that
+    // dont cross any existing connections, and if even that is not possible with the shortest single connections like
+    // `connect_outposts` does, which is the only case where a slice is not connected identically
+    if components > 1 {
+        let mut all_edges = vec![];
+        for a in 0..outposts.len() {
+            for b in a + 1..outposts.len() {
+                all_edges.push((a, b));
+            }
+        }
+        all_edges.sort_by(|a, b| length(a).total_cmp(&length(b)));
+        let single_edges = all_edges.iter().map(|edge| vec![*edge]);
+        for group in edge_groups(outposts, &copies_of, all_edges.iter().copied())
+            .into_iter()
+            .chain(single_edges)
+        {
+            if components == 1 {
+                break;
+            }
+            if group.iter().all(|(a, b)| sets.find(*a) == sets.find(*b))
+                || crosses(&connections, &group)
+            {
+                continue;
+            }
+            for (a, b) in group.iter() {
+                if sets.union(*a, *b) {
+                    components -= 1;
+                }
+            }
+            connections.extend(group);
+        }
+    }
+
+    let extra_count =
+        (extra_groups.len() as f32 * density.extra_connection_ratio()).round() as usize;
+    let mut added = 0;
+    for group in extra_groups {
+        if added >= extra_count {
+            break;
+        }
+        if crosses(&connections, &group) {
+            continue;
+        }
+        connections.extend(group);
+        added += 1;
+    }
+
+    connections.sort();
+    connections
+}
+
+/// Groups every edge together with all of its copies, keeping the order of `edges`. Groups whose copies cross each other
+/// are left out
+fn edge_groups(
+    outposts: &[Vec2],
+    copies_of: &[Vec<Option<usize>>],
+    edges: impl Iterator<Item = (usize, usize)>,
+) -> Vec<Vec<(usize, usize)>> {
+    let mut seen = HashSet::new();
+    let mut groups: Vec<Vec<(usize, usize)>> = vec![];
+    for (a, b) in edges {
+        if seen.contains(&(a, b)) {
+            continue;
+        }
+        let mut group: Vec<(usize, usize)> = copies_of
+            .iter()
+            .filter_map(|copy_of| {
+                let (a, b) = (copy_of[a]?, copy_of[b]?);
+                Some((a.min(b), a.max(b)))
+            })
+            .filter(|(a, b)| a != b)
+            .collect();
+        group.sort();
+        group.dedup();
+        seen.extend(group.iter().copied());
+        let crosses_itself = group.iter().enumerate().any(|(index, edge)| {
+            group
+                .iter()
+                .skip(index + 1)
+                .any(|other| connections_cross(outposts, *edge, *other))
+        });
+        if !crosses_itself {
+            groups.push(group);
+        }
+    }
+    groups
+}
+
+/// Returns every transform that maps the slice onto a copy of itself. There is one rotation per player, and when mirrored each
+/// rotation is followed by its mirrored version
+fn symmetry_transforms(
+    map_size: Vec2,
+    symmetry: MapSymmetry,
+    player_count: usize,
+) -> Vec<Box<dyn Fn(Vec2) -> Vec2>> {
+    let center = map_size / 2.0;
+    let rotations = match symmetry {
+        MapSymmetry::None => 1,
+        MapSymmetry::Rotational | MapSymmetry::Mirrored => player_count,
+    };
+
+    let mut transforms: Vec<Box<dyn Fn(Vec2) -> Vec2>> = vec![];
+    for rotation in 0..rotations {
+        let rotation = Vec2::from_angle(TAU * rotation as f32 / rotations as f32);
+        transforms.push(Box::new(move |point| {
+            center + rotation.rotate(point - center)
+        }));
+    }
+    if symmetry == MapSymmetry::Mirrored {
+        // The mirrored copies are ordered so that each one sits next to the rotation it mirrors
+        let mirrored: Vec<Box<dyn Fn(Vec2) -> Vec2>> = (0..rotations)
+            .map(|rotation| {
+                let rotation = Vec2::from_angle(TAU * rotation as f32 / rotations as f32);
+                Box::new(move |point: Vec2| {
+                    let offset = point - center;
+                    center + rotation.rotate(Vec2::new(offset.x, -offset.y))
+                }) as Box<dyn Fn(Vec2) -> Vec2>
+            })
+            .collect();
+        transforms = transforms
+            .into_iter()
+            .zip(mirrored)
+            .flat_map(|(rotated, mirrored)| [rotated, mirrored])
+            .collect();
+    }
+    transforms
+}
+
+/// Returns the index of the outpost at `position`
+fn find_outpost(outposts: &[Vec2], position: Vec2) -> Option<usize> {
+    outposts
+        .iter()
+        .position(|outpost| outpost.distance(position) < MATCH_DISTANCE)
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::math::Vec2;
+    use general::game_meta::{ConnectionDensity, MapSymmetry};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use crate::{
+        map_symmetry::{find_outpost, generate_symmetric_layout, symmetry_transforms},
+        outpost_connections::{connections_cross, DisjointSets},
+    };
+
+    #[test]
+    fn test_symmetric_layouts() {
+        let map_size = Vec2::new(300.0, 300.0);
+        for symmetry in [MapSymmetry::Rotational, MapSymmetry::Mirrored] {
+            for density in [ConnectionDensity::Sparse, ConnectionDensity::Dense] {
+                for player_count in 2..=6 {
+                    for seed in 0..10 {
+                        let mut rng = StdRng::seed_from_u64(seed);
+                        let layout = generate_symmetric_layout(
+                            map_size,
+                            55,
+                            &density,
+                            symmetry,
+                            player_count,
+                            &mut rng,
+                        );
+                        assert_eq!(layout.start_slots.len(), player_count as usize);
+
+                        // Connected and planar
+                        let mut sets = DisjointSets::new(layout.outposts.len());
+                        for (index, connection) in layout.connections.iter().enumerate() {
+                            sets.union(connection.0, connection.1);
+                            for other in layout.connections.iter().skip(index + 1) {
+                                assert!(!connections_cross(&layout.outposts, *connection, *other));
+                            }
+                        }
+                        let root = sets.find(0);
+                        assert!(
+                            (0..layout.outposts.len()).all(|index| sets.find(index) == root),
+                            "{:?} {:?} map for {} players with seed {} is not connected",
+                            symmetry,
+                            density,
+                            player_count,
+                            seed
+                        );
+
+                        // Every outpost and connection has a copy in every slice
+                        for transform in
+                            symmetry_transforms(map_size, symmetry, player_count as usize)
+                        {
+                            for outpost in layout.outposts.iter() {
+                                assert!(
+                                    find_outpost(&layout.outposts, transform(*outpost)).is_some()
+                                );
+                            }
+                            for (a, b) in layout.connections.iter() {
+                                let a =
+                                    find_outpost(&layout.outposts, transform(layout.outposts[*a]))
+                                        .unwrap();
+                                let b =
+                                    find_outpost(&layout.outposts, transform(layout.outposts[*b]))
+                                        .unwrap();
+                                assert!(layout.connections.contains(&(a.min(b), a.max(b))));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,263 @@
+//! Responsible for connecting outposts together into the graph that armies travel along.
+//!
+//! The graph is built from the Delaunay triangulation of the outposts. The triangulation is pruned down to its minimum spanning
+//! tree so that every outpost is reachable, and then the shortest of the remaining triangulation edges are added back depending
+//! on the [`ConnectionDensity`]. Every connection comes from the triangulation so connections never cross each other.
+
+use bevy::math::{DVec2, Vec2};
+use general::game_meta::ConnectionDensity;
+
+/// Returns every connection between the given outposts as pairs of indexes into `positions`.
+///
+/// The first index of a connection is always the lower of the two.
+pub fn connect_outposts(positions: &[Vec2], density: &ConnectionDensity) -> Vec<(usize, usize)> {
+    if positions.len() < 2 {
+        return vec![];
+    }
+
+    let length = |edge: &(usize, usize)| positions[edge.0].distance_squared(positions[edge.1]);
+    let mut candidate_edges = delaunay_edges(positions);
+    candidate_edges.sort_by(|a, b| length(a).total_cmp(&length(b)));
+
+    // Kruskal's algorithm. The minimum spanning tree of a set of points is always a subset of its Delaunay triangulation
+    let mut sets = DisjointSets::new(positions.len());
+    let mut connections = vec![];
+    let mut extra_edges = vec![];
+    for edge in candidate_edges {
+        if sets.union(edge.0, edge.1) {
+            connections.push(edge);
+        } else {
+            extra_edges.push(edge);
+        }
+    }
+
+    // A degenerate triangulation (every point in a line) can leave outposts disconnected. Connect them with the shortest
+    // connections that dont cross any existing connections
+    if connections.len() < positions.len() - 1 {
+        let mut all_edges = vec![];
+        for a in 0..positions.len() {
+            for b in a + 1..positions.len() {
+                all_edges.push((a, b));
+            }
+        }
+        all_edges.sort_by(|a, b| length(a).total_cmp(&length(b)));
+        for edge in all_edges {
+            if sets.find(edge.0) == sets.find(edge.1) {
+                continue;
+            }
+            if connections
+                .iter()
+                .any(|existing| connections_cross(positions, edge, *existing))
+            {
+                continue;
+            }
+            sets.union(edge.0, edge.1);
+            connections.push(edge);
+        }
+    }
+
+    let extra_count =
+        (extra_edges.len() as f32 * density.extra_connection_ratio()).round() as usize;
+    connections.extend(extra_edges.into_iter().take(extra_count));
+    connections
+}
+
+/// Returns true if the two connections cross each other somewhere other than at a shared outpost
+pub(crate) fn connections_cross(positions: &[Vec2], a: (usize, usize), b: (usize, usize)) -> bool {
+    if a.0 == b.0 || a.0 == b.1 || a.1 == b.0 || a.1 == b.1 {
+        return false;
+    }
+    let (p1, p2) = (positions[a.0].as_dvec2(), positions[a.1].as_dvec2());
+    let (p3, p4) = (positions[b.0].as_dvec2(), positions[b.1].as_dvec2());
+    let d1 = (p2 - p1).perp_dot(p3 - p1);
+    let d2 = (p2 - p1).perp_dot(p4 - p1);
+    let d3 = (p4 - p3).perp_dot(p1 - p3);
+    let d4 = (p4 - p3).perp_dot(p2 - p3);
+    d1 * d2 <= 0.0 && d3 * d4 <= 0.0
+}
+
+/// A triangle in the triangulation, made from three indexes into the point list
+struct Triangle {
+    vertices: [usize; 3],
+    circumcenter: DVec2,
+    circumradius_squared: f64,
+}
+
+impl Triangle {
+    fn new(vertices: [usize; 3], points: &[DVec2]) -> Triangle {
+        let (a, b, c) = (
+            points[vertices[0]],
+            points[vertices[1]],
+            points[vertices[2]],
+        );
+        let d = 2.0 * (a.x * (b.y - c.y) + b.x * (c.y - a.y) + c.x * (a.y - b.y));
+        if d.abs() < f64::EPSILON {
+            // Degenerate triangles contain everything so that they are always replaced
+            return Triangle {
+                vertices,
+                circumcenter: a,
+                circumradius_squared: f64::INFINITY,
+            };
+        }
+        let circumcenter = DVec2::new(
+            (a.length_squared() * (b.y - c.y)
+                + b.length_squared() * (c.y - a.y)
+                + c.length_squared() * (a.y - b.y))
+                / d,
+            (a.length_squared() * (c.x - b.x)
+                + b.length_squared() * (a.x - c.x)
+                + c.length_squared() * (b.x - a.x))
+                / d,
+        );
+        Triangle {
+            vertices,
+            circumcenter,
+            circumradius_squared: circumcenter.distance_squared(a),
+        }
+    }
+
+    fn edges(&self) -> [(usize, usize); 3] {
+        let [a, b, c] = self.vertices;
+        [ordered_edge(a, b), ordered_edge(b, c), ordered_edge(c, a)]
+    }
+}
+
+fn ordered_edge(a: usize, b: usize) -> (usize, usize) {
+    (a.min(b), a.max(b))
+}
+
+/// Bowyer-Watson Delaunay triangulation. Returns every unique edge of the triangulation
+pub(crate) fn delaunay_edges(positions: &[Vec2]) -> Vec<(usize, usize)> {
+    let mut points: Vec<DVec2> = positions.iter().map(|point| point.as_dvec2()).collect();
+
+    // A triangle large enough to contain every point which the triangulation is built inside of
+    let (mut min, mut max) = (points[0], points[0]);
+    for point in points.iter() {
+        min = min.min(*point);
+        max = max.max(*point);
+    }
+    let delta = (max - min).max_element().max(1.0);
+    let middle = (min + max) / 2.0;
+    let super_start = points.len();
+    points.push(DVec2::new(middle.x - 20.0 * delta, middle.y - delta));
+    points.push(DVec2::new(middle.x, middle.y + 20.0 * delta));
+    points.push(DVec2::new(middle.x + 20.0 * delta, middle.y - delta));
+
+    let mut triangles = vec![Triangle::new(
+        [super_start, super_start + 1, super_start + 2],
+        &points,
+    )];
+
+    for point_index in 0..super_start {
+        let point = points[point_index];
+        let (bad_triangles, good_triangles): (Vec<Triangle>, Vec<Triangle>) =
+            triangles.into_iter().partition(|triangle| {
+                triangle.circumcenter.distance_squared(point) < triangle.circumradius_squared
+            });
+        triangles = good_triangles;
+
+        // The boundary of the hole left by the bad triangles are the edges that only one bad triangle has
+        let bad_edges: Vec<(usize, usize)> = bad_triangles
+            .iter()
+            .flat_map(|triangle| triangle.edges())
+            .collect();
+        for edge in bad_edges.iter() {
+            if bad_edges.iter().filter(|other| *other == edge).count() == 1 {
+                triangles.push(Triangle::new([edge.0, edge.1, point_index], &points));
+            }
+        }
+    }
+
+    let mut edges: Vec<(usize, usize)> = triangles
+        .iter()
+        .flat_map(|triangle| triangle.edges())
+        .filter(|edge| edge.1 < super_start)
+        .collect();
+    edges.sort();
+    edges.dedup();
+    edges
+}
+
+/// Union-find used to track which outposts are already connected to each other
+pub(crate) struct DisjointSets {
+    parents: Vec<usize>,
+}
+
+impl DisjointSets {
+    pub(crate) fn new(size: usize) -> DisjointSets {
+        DisjointSets {
+            parents: (0..size).collect(),
+        }
+    }
+
+    pub(crate) fn find(&mut self, index: usize) -> usize {
+        let mut root = index;
+        while self.parents[root] != root {
+            root = self.parents[root];
+        }
+        // Flatten the path so future lookups are faster
+        let mut current = index;
+        while self.parents[current] != root {
+            let next = self.parents[current];
+            self.parents[current] = root;
+            current = next;
+        }
+        root
+    }
+
+    /// Joins the sets containing `a` and `b`. Returns false if they were already in the same set
+    pub(crate) fn union(&mut self, a: usize, b: usize) -> bool {
+        let (root_a, root_b) = (self.find(a), self.find(b));
+        if root_a == root_b {
+            return false;
+        }
+        self.parents[root_a] = root_b;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::math::Vec2;
+    use general::game_meta::ConnectionDensity;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use crate::{
+        outpost_connections::{connect_outposts, connections_cross, DisjointSets},
+        outpost_placement::place_outposts,
+    };
+
+    #[test]
+    fn test_outpost_connections() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let positions = place_outposts(Vec2::new(300.0, 300.0), 55, &mut rng);
+
+        let sparse = connect_outposts(&positions, &ConnectionDensity::Sparse);
+        let dense = connect_outposts(&positions, &ConnectionDensity::Dense);
+        assert!(dense.len() > sparse.len());
+
+        for connections in [sparse, dense] {
+            // Connected
+            let mut sets = DisjointSets::new(positions.len());
+            for connection in connections.iter() {
+                sets.union(connection.0, connection.1);
+            }
+            let root = sets.find(0);
+            assert!((0..positions.len()).all(|index| sets.find(index) == root));
+
+            // Planar
+            for (index, connection) in connections.iter().enumerate() {
+                for other in connections.iter().skip(index + 1) {
+                    assert!(!connections_cross(&positions, *connection, *other));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_outpost_connections_in_a_line() {
+        let positions: Vec<Vec2> = (0..5).map(|i| Vec2::new(i as f32, i as f32)).collect();
+        let connections = connect_outposts(&positions, &ConnectionDensity::Dense);
+        assert_eq!(connections, vec![(0, 1), (1, 2), (2, 3), (3, 4)]);
+    }
+}
@@ -0,0 +1,189 @@
+//! Responsible for placing outposts across the map.
+//!
+//! Uses Poisson-disc sampling so that outposts fill the entire map while keeping a minimum spacing between each other
+
+use bevy::math::Vec2;
+use rand::{seq::SliceRandom, Rng};
+
+/// How many candidate points are tried around an active point before it is retired
+const CANDIDATE_ATTEMPTS: u32 = 30;
+/// Multiplier applied to the ideal spacing of an evenly filled map to get the starting minimum spacing
+const SPACING_FACTOR: f32 = 0.7;
+/// Multiplier applied to the minimum spacing every time the map could not fit the requested amount of outposts
+const SPACING_FALLOFF: f32 = 0.9;
+/// The spacing at which placement gives up and returns however many outposts fit
+const SMALLEST_SPACING: f32 = 0.01;
+
+/// Returns the positions of `outpost_count` outposts spread across a map of `map_size`.
+///
+/// Every position is inside `(0, 0)..map_size` and is kept at least [`minimum_outpost_spacing`] away from every other position.
+/// If the map cannot fit that many outposts with that spacing then the spacing is slowly lowered until they fit.
+pub fn place_outposts(map_size: Vec2, outpost_count: u16, rng: &mut impl Rng) -> Vec<Vec2> {
+    place_outposts_within(
+        map_size,
+        outpost_count,
+        minimum_outpost_spacing(map_size, outpost_count),
+        |_, _| true,
+        rng,
+    )
+}
+
+/// Returns the positions of `outpost_count` outposts spread across the part of a map of `map_size` where `inside` is true.
+///
+/// `inside` is given a position and the spacing currently being used. Outposts start `min_distance` apart and the spacing is
+/// slowly lowered until they fit, the same as [`place_outposts`]
+pub fn place_outposts_within(
+    map_size: Vec2,
+    outpost_count: u16,
+    mut min_distance: f32,
+    inside: impl Fn(Vec2, f32) -> bool,
+    rng: &mut impl Rng,
+) -> Vec<Vec2> {
+    let outpost_count = outpost_count as usize;
+    if outpost_count == 0 || map_size.x <= 0.0 || map_size.y <= 0.0 {
+        return vec![];
+    }
+
+    loop {
+        let mut points = poisson_disc_sample(map_size, min_distance, &inside, rng);
+        if points.len() >= outpost_count {
+            // Sampling grows outwards from the first point so we shuffle before removing extras to keep the map evenly filled
+            points.shuffle(rng);
+            points.truncate(outpost_count);
+            return points;
+        }
+        if min_distance < SMALLEST_SPACING {
+            return points;
+        }
+        min_distance *= SPACING_FALLOFF;
+    }
+}
+
+/// The minimum spacing that outposts try to keep from each other for the given map
+pub fn minimum_outpost_spacing(map_size: Vec2, outpost_count: u16) -> f32 {
+    (map_size.x * map_size.y / outpost_count.max(1) as f32).sqrt() * SPACING_FACTOR
+}
+
+/// How many random points are tried to find a starting point inside the sampled area before giving up
+const START_ATTEMPTS: u32 = 1000;
+
+/// Bridson's Poisson-disc sampling. Fills the part of the map where `inside` is true with as many points as fit while keeping
+/// them `min_distance` apart
+fn poisson_disc_sample(
+    map_size: Vec2,
+    min_distance: f32,
+    inside: &impl Fn(Vec2, f32) -> bool,
+    rng: &mut impl Rng,
+) -> Vec<Vec2> {
+    let cell_size = min_distance / std::f32::consts::SQRT_2;
+    let grid_width = (map_size.x / cell_size).ceil() as usize;
+    let grid_height = (map_size.y / cell_size).ceil() as usize;
+    let cell_of = |point: Vec2| {
+        (
+            ((point.x / cell_size) as usize).min(grid_width - 1),
+            ((point.y / cell_size) as usize).min(grid_height - 1),
+        )
+    };
+
+    // Each grid cell is small enough that it can only ever hold a single point
+    let mut grid: Vec<Option<usize>> = vec![None; grid_width * grid_height];
+    let mut points: Vec<Vec2> = vec![];
+    let mut active: Vec<usize> = vec![];
+
+    let Some(first) = (0..START_ATTEMPTS)
+        .map(|_| {
+            Vec2::new(
+                rng.gen_range(0.0..map_size.x),
+                rng.gen_range(0.0..map_size.y),
+            )
+        })
+        .find(|point| inside(*point, min_distance))
+    else {
+        return vec![];
+    };
+    let (x, y) = cell_of(first);
+    grid[y * grid_width + x] = Some(0);
+    points.push(first);
+    active.push(0);
+
+    while !active.is_empty() {
+        let active_index = rng.gen_range(0..active.len());
+        let origin = points[active[active_index]];
+        let mut found = false;
+
+        for _ in 0..CANDIDATE_ATTEMPTS {
+            let angle = rng.gen_range(0.0..std::f32::consts::TAU);
+            let distance = rng.gen_range(min_distance..min_distance * 2.0);
+            let candidate = origin + Vec2::new(angle.cos(), angle.sin()) * distance;
+
+            if candidate.x < 0.0
+                || candidate.y < 0.0
+                || candidate.x >= map_size.x
+                || candidate.y >= map_size.y
+                || !inside(candidate, min_distance)
+            {
+                continue;
+            }
+
+            let (x, y) = cell_of(candidate);
+            let mut too_close = false;
+            'neighbours: for neighbour_y in y.saturating_sub(2)..(y + 3).min(grid_height) {
+                for neighbour_x in x.saturating_sub(2)..(x + 3).min(grid_width) {
+                    if let Some(neighbour) = grid[neighbour_y * grid_width + neighbour_x] {
+                        if points[neighbour].distance(candidate) < min_distance {
+                            too_close = true;
+                            break 'neighbours;
+                        }
+                    }
+                }
+            }
+            if too_close {
+                continue;
+            }
+
+            grid[y * grid_width + x] = Some(points.len());
+            active.push(points.len());
+            points.push(candidate);
+            found = true;
+            break;
+        }
+
+        if !found {
+            active.swap_remove(active_index);
+        }
+    }
+
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::math::Vec2;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use crate::outpost_placement::{minimum_outpost_spacing, place_outposts};
+
+    #[test]
+    fn test_outpost_placement() {
+        let map_size = Vec2::new(300.0, 300.0);
+        let mut rng = StdRng::seed_from_u64(0);
+        let outposts = place_outposts(map_size, 55, &mut rng);
+
+        assert_eq!(outposts.len(), 55);
+        for (index, outpost) in outposts.iter().enumerate() {
+            assert!(outpost.x >= 0.0 && outpost.x < map_size.x);
+            assert!(outpost.y >= 0.0 && outpost.y < map_size.y);
+            for other in outposts.iter().skip(index + 1) {
+                assert!(outpost.distance(*other) >= minimum_outpost_spacing(map_size, 55));
+            }
+        }
+    }
+
+    #[test]
+    fn test_outpost_placement_shrinks_spacing_to_fit() {
+        // Far more outposts than the map can fit at the starting spacing
+        let mut rng = StdRng::seed_from_u64(0);
+        let outposts = place_outposts(Vec2::new(50.0, 50.0), 400, &mut rng);
+        assert_eq!(outposts.len(), 400);
+    }
+}
@@ -0,0 +1,146 @@
+//! Responsible for picking a fair home outpost for every player when a game starts.
+//!
+//! Homes are picked so that players are as far from their nearest opponents as possible while keeping both the distance to
+//! the nearest opponent and the amount of nearby neutral outposts as even as possible between players.
+
+use std::collections::VecDeque;
+
+/// How many connections away a neutral outpost can be to count as near a home outpost
+const NEARBY_NEUTRAL_RANGE: usize = 2;
+/// How much the smallest distance between two homes is worth compared to a difference of one between players
+const DISTANCE_WEIGHT: i64 = 4;
+
+/// Returns the indexes of the home outposts for `player_count` players, picked from `candidates`.
+///
+/// `connections` are pairs of indexes into the list of `outpost_count` outposts. Returns None if there are less candidates than players
+pub fn allocate_start_positions(
+    outpost_count: usize,
+    connections: &[(usize, usize)],
+    candidates: &[usize],
+    player_count: usize,
+) -> Option<Vec<usize>> {
+    if player_count == 0 {
+        return Some(vec![]);
+    }
+    if candidates.len() < player_count {
+        return None;
+    }
+
+    let distances = graph_distances(outpost_count, connections);
+
+    let mut best: Option<(i64, Vec<usize>)> = None;
+    // Try every candidate as the first home and then greedily add the candidate furthest from every existing home
+    for first in candidates.iter() {
+        let mut homes = vec![*first];
+        while homes.len() < player_count {
+            let next = candidates
+                .iter()
+                .filter(|candidate| !homes.contains(candidate))
+                .max_by_key(|candidate| {
+                    let nearest_home = homes
+                        .iter()
+                        .map(|home| distances[**candidate][*home])
+                        .min()
+                        .unwrap_or(usize::MAX);
+                    // Prefer the lowest index on ties so the result is stable
+                    (nearest_home, std::cmp::Reverse(**candidate))
+                })?;
+            homes.push(*next);
+        }
+
+        let score = fairness_score(&homes, &distances);
+        match &best {
+            Some((best_score, _)) if *best_score >= score => {}
+            _ => best = Some((score, homes)),
+        }
+    }
+
+    best.map(|(_, homes)| homes)
+}
+
+/// Scores how fair a set of homes is. Higher is fairer
+fn fairness_score(homes: &[usize], distances: &[Vec<usize>]) -> i64 {
+    if homes.len() < 2 {
+        return 0;
+    }
+
+    let mut nearest_opponents = vec![];
+    let mut nearby_neutrals = vec![];
+    for home in homes.iter() {
+        let nearest_opponent = homes
+            .iter()
+            .filter(|other| *other != home)
+            .map(|other| distances[*home][*other])
+            .min()
+            .unwrap_or(usize::MAX)
+            .min(distances.len()) as i64;
+        nearest_opponents.push(nearest_opponent);
+
+        // Neutral outposts near this home that are closer to it than to any other home
+        let neutrals = (0..distances.len())
+            .filter(|outpost| !homes.contains(outpost))
+            .filter(|outpost| distances[*home][*outpost] <= NEARBY_NEUTRAL_RANGE)
+            .filter(|outpost| {
+                homes
+                    .iter()
+                    .filter(|other| *other != home)
+                    .all(|other| distances[*other][*outpost] > distances[*home][*outpost])
+            })
+            .count() as i64;
+        nearby_neutrals.push(neutrals);
+    }
+
+    let spread =
+        |values: &[i64]| values.iter().max().unwrap_or(&0) - values.iter().min().unwrap_or(&0);
+
+    nearest_opponents.iter().min().unwrap_or(&0) * DISTANCE_WEIGHT
+        - spread(&nearest_opponents)
+        - spread(&nearby_neutrals)
+}
+
+/// The amount of connections between every pair of outposts. Unreachable outposts are [`usize::MAX`] apart
+pub(crate) fn graph_distances(
+    outpost_count: usize,
+    connections: &[(usize, usize)],
+) -> Vec<Vec<usize>> {
+    let mut neighbours = vec![vec![]; outpost_count];
+    for (a, b) in connections.iter() {
+        neighbours[*a].push(*b);
+        neighbours[*b].push(*a);
+    }
+
+    (0..outpost_count)
+        .map(|start| {
+            let mut distances = vec![usize::MAX; outpost_count];
+            distances[start] = 0;
+            let mut queue = VecDeque::from([start]);
+            while let Some(current) = queue.pop_front() {
+                for neighbour in neighbours[current].iter() {
+                    if distances[*neighbour] == usize::MAX {
+                        distances[*neighbour] = distances[current] + 1;
+                        queue.push_back(*neighbour);
+                    }
+                }
+            }
+            distances
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::start_positions::allocate_start_positions;
+
+    #[test]
+    fn test_start_positions_on_a_line() {
+        // 0 - 1 - 2 - 3 - 4 - 5 - 6
+        let connections: Vec<(usize, usize)> = (0..6).map(|i| (i, i + 1)).collect();
+        let candidates: Vec<usize> = (0..7).collect();
+
+        let mut homes = allocate_start_positions(7, &connections, &candidates, 2).unwrap();
+        homes.sort();
+        assert_eq!(homes, vec![0, 6]);
+
+        assert!(allocate_start_positions(7, &connections, &candidates[..1], 2).is_none());
+    }
+}
@@ -1,3 +1,74 @@
+//! Actions are how players interact with a game. Every action is scheduled for a tick and is executed by the game simulation
+//! once that tick arrives
+
+use bevy::utils::Uuid;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    auth_server::AccountId,
+    objects::{core_components::ObjectId, units::UnitComposition},
+};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub enum Action {
-    MoveArmy,
+    /// Sends `units` from the garrison of the `from` outpost as a new army that travels along connections to the `to` outpost
+    MoveArmy {
+        from: ObjectId,
+        to: ObjectId,
+        units: UnitComposition,
+    },
+    /// Sends `units` from the garrison of the `from` outpost as a new army that travels straight towards the `target` army,
+    /// fights it where they meet, and then returns to the `from` outpost
+    InterceptArmy {
+        from: ObjectId,
+        target: ObjectId,
+        units: UnitComposition,
+    },
+}
+
+impl Action {
+    /// The units the action sends out
+    pub fn units(&self) -> &UnitComposition {
+        match self {
+            Action::MoveArmy { units, .. } => units,
+            Action::InterceptArmy { units, .. } => units,
+        }
+    }
+}
+
+/// Why an action could not be executed
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ActionFailure {
+    /// The action does not send any units
+    NoUnits,
+    /// The player does not own the outpost the action sends units from
+    NotOutpostOwner,
+    /// The garrison of the outpost the action sends units from is too small
+    InsufficientUnits,
+    /// There is no route to the destination through outposts the player owns
+    NoRoute,
+    /// The army being intercepted does not exist, belongs to the player, has arrived or can not be reached in time
+    InvalidTarget,
+}
+
+/// Uniquely identifies a scheduled [`PlayerAction`]. Issued by the server when the action is accepted
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct ActionId {
+    pub id: Uuid,
+}
+
+impl ActionId {
+    /// Creates a new random id
+    pub fn generate() -> ActionId {
+        ActionId { id: Uuid::new_v4() }
+    }
+}
+
+/// A player action, is stored by the server and simulated ahead of time but executed only when the tick arrives.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PlayerAction {
+    pub action_id: ActionId,
+    pub tick_scheduled: u64,
+    pub issued_by_player: AccountId,
+    pub action: Action,
 }
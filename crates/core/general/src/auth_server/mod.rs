@@ -1,11 +1,12 @@
+use bevy::ecs::component::Component;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 pub mod game;
 pub mod player_data;
 
-/// A wrapper for an id assigned from the auth server
-#[derive(Serialize, Deserialize, Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+/// A wrapper for an id assigned from the auth server. Is also the component identifying player entities in game worlds
+#[derive(Component, Serialize, Deserialize, Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub struct AccountId {
     pub id: Uuid,
 }
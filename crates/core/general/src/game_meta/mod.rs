@@ -49,7 +49,7 @@ pub struct GameSettings {
 }
 
 /// Settings that can be changed and must be supplied when starting a new game
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone, Debug, Resource)]
 pub struct NewGameSettings {
     pub max_player_count: u8,
     pub map_point_count: MapPointCount,
@@ -57,10 +57,43 @@ pub struct NewGameSettings {
     pub connection_density: ConnectionDensity,
     pub ticks_per_tick: u64,
     pub simulation_tick_amount: u64,
+    /// The seed used to generate the map. A random seed is picked when the game is created if none is given
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// The name of a hand-authored map file to play on. When set the map is loaded from the file instead of being generated
+    #[serde(default)]
+    pub custom_map: Option<String>,
+    /// Whether the map is generated so that every player gets the same geometry. The map has one slice for every player in
+    /// `max_player_count`, so it is only perfectly symmetric once the game is full
+    #[serde(default)]
+    pub map_symmetry: MapSymmetry,
+}
+
+impl Default for NewGameSettings {
+    /// The settings games saved before their settings were stored are loaded with
+    fn default() -> Self {
+        NewGameSettings {
+            max_player_count: 2,
+            map_point_count: MapPointCount::Normal,
+            map_size: MapSize::Medium,
+            connection_density: ConnectionDensity::Sparse,
+            ticks_per_tick: 1,
+            simulation_tick_amount: 1,
+            seed: None,
+            custom_map: None,
+            map_symmetry: MapSymmetry::None,
+        }
+    }
+}
+
+/// The seed a games map was generated with. The same seed and [`NewGameSettings`] will always generate the same map
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Resource)]
+pub struct GameSeed {
+    pub seed: u64,
 }
 
 /// The map dimensions. Representing the total physical size of the map
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub enum MapSize {
     Small,
     Medium,
@@ -80,14 +113,37 @@ impl MapSize {
 }
 
 /// How many connections will be drawn between outposts
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub enum ConnectionDensity {
     Dense,
     Sparse,
 }
 
+impl ConnectionDensity {
+    /// The fraction of possible connections, beyond those needed to connect every outpost, that are kept
+    pub fn extra_connection_ratio(&self) -> f32 {
+        match self {
+            ConnectionDensity::Dense => 0.6,
+            ConnectionDensity::Sparse => 0.2,
+        }
+    }
+}
+
+/// How a generated map is made symmetric between players. Symmetric maps are built from one slice per player arranged around
+/// the center of the map
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum MapSymmetry {
+    /// The map is generated freely with no symmetry
+    #[default]
+    None,
+    /// Every players slice is a rotated copy of the same slice
+    Rotational,
+    /// Every players slice is a rotated copy of the same slice, and each slice is mirrored down its middle
+    Mirrored,
+}
+
 /// The amount of points on a map
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub enum MapPointCount {
     Light,
     Normal,
@@ -107,7 +163,7 @@ impl MapPointCount {
 }
 
 /// Holds the [`AccountId`]s of every player that is actually playing in the game
-#[derive(Serialize, Deserialize, Clone, Component, Default)]
+#[derive(Serialize, Deserialize, Clone, Component, Resource, Default)]
 pub struct GamePlayers {
     pub players: Vec<AccountId>,
 }
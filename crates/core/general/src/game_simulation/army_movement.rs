@@ -0,0 +1,404 @@
+//! Responsible for executing army actions and moving armies between outposts.
+//!
+//! A [`Action::MoveArmy`] detaches units from an outposts garrison into a new army. The army travels along
+//! [`OutpostConnections`] and every waypoint is written into its linear [`ObjectPosition`] curve when the move is executed so
+//! that arrival times, and where the army is at any tick in between, are known ahead of time.
+
+use bevy::{
+    ecs::{
+        bundle::Bundle,
+        query::With,
+        system::{Commands, Query, ResMut, SystemState},
+        world::World,
+    },
+    math::Vec2,
+    utils::HashMap,
+};
+use bevy_state_curves::prelude::{CurveTrait, LinearCurve, SteppedCurve};
+
+use crate::{
+    actions::{Action, ActionFailure, PlayerAction},
+    auth_server::AccountId,
+    objects::{
+        army::{Army, ArmyRoute, ArmyUnits, ArmyWaypoint},
+        core_components::{ObjectGeneral, ObjectId, ObjectPosition},
+        outpost::{Outpost, OutpostConnections, OutpostGarrison},
+        units::UnitComposition,
+        ObjectIdService,
+    },
+};
+
+use super::{insert_simulated_keyframe, interception::intercept_army, DueActions, FailedActions};
+
+/// The state of an outpost at a single tick, used for finding routes
+pub struct OutpostSnapshot {
+    pub position: Vec2,
+    pub connections: Vec<ObjectId>,
+    pub owner: Option<AccountId>,
+}
+
+/// The amount of ticks it takes an army with the given speed to travel between two positions. Always at least one tick
+pub fn travel_ticks(from: Vec2, to: Vec2, speed: f32) -> u64 {
+    ((from.distance(to) / speed).ceil() as u64).max(1)
+}
+
+/// Finds the shortest route from `from` to `to` along outpost connections. Returns every outpost on the route including
+/// `from` and `to`.
+///
+/// Armies can only pass through outposts owned by `player`, the destination can be owned by anyone
+pub fn find_route(
+    outposts: &HashMap<ObjectId, OutpostSnapshot>,
+    from: ObjectId,
+    to: ObjectId,
+    player: &AccountId,
+) -> Option<Vec<ObjectId>> {
+    outposts.get(&to)?;
+    let mut distances: HashMap<ObjectId, f32> = HashMap::new();
+    let mut previous: HashMap<ObjectId, ObjectId> = HashMap::new();
+    let mut visited: Vec<ObjectId> = vec![];
+    distances.insert(from, 0.0);
+
+    // Dijkstra's algorithm. Ties are broken by the lowest id so routes are deterministic
+    loop {
+        let (current, distance) = distances
+            .iter()
+            .filter(|(id, _)| !visited.contains(id))
+            .min_by(|a, b| a.1.total_cmp(b.1).then(a.0.cmp(b.0)))
+            .map(|(id, distance)| (*id, *distance))?;
+        if current == to {
+            break;
+        }
+        visited.push(current);
+
+        let outpost = outposts.get(&current)?;
+        if current != from && outpost.owner.as_ref() != Some(player) {
+            continue;
+        }
+        for neighbour_id in outpost.connections.iter() {
+            let Some(neighbour) = outposts.get(neighbour_id) else {
+                continue;
+            };
+            let new_distance = distance + outpost.position.distance(neighbour.position);
+            match distances.get(neighbour_id) {
+                Some(old_distance) if *old_distance <= new_distance => {}
+                _ => {
+                    distances.insert(*neighbour_id, new_distance);
+                    previous.insert(*neighbour_id, current);
+                }
+            }
+        }
+    }
+
+    let mut route = vec![to];
+    while let Some(previous) = previous.get(route.last()?) {
+        route.push(*previous);
+    }
+    route.reverse();
+    Some(route)
+}
+
+/// The curves of every outpost that army actions read and change
+pub(crate) type OutpostCurves<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static ObjectId,
+        &'static SteppedCurve<ObjectPosition>,
+        &'static SteppedCurve<OutpostConnections>,
+        &'static SteppedCurve<ObjectGeneral>,
+        &'static mut SteppedCurve<OutpostGarrison>,
+    ),
+    With<Outpost>,
+>;
+
+/// The curves of every army that army actions read
+pub(crate) type ArmyCurves<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static ObjectId,
+        &'static LinearCurve<ObjectPosition>,
+        &'static SteppedCurve<ObjectGeneral>,
+        &'static SteppedCurve<ArmyUnits>,
+        &'static SteppedCurve<ArmyRoute>,
+    ),
+    With<Army>,
+>;
+
+/// Executes every due army action, spawning a new army for each valid one. Actions that can not be executed are recorded
+/// in [`FailedActions`]
+pub fn execute_army_actions(
+    mut commands: Commands,
+    mut due_actions: ResMut<DueActions>,
+    mut failed_actions: ResMut<FailedActions>,
+    mut id_service: ResMut<ObjectIdService>,
+    mut outposts: OutpostCurves,
+    armies: ArmyCurves,
+) {
+    let mut actions: Vec<_> = due_actions.actions.drain(..).collect();
+    actions.sort_by_key(|action| action.tick_scheduled);
+
+    for player_action in actions {
+        match plan_army_action(&outposts, &armies, &player_action) {
+            Ok((from, army)) => {
+                let units = player_action.action.units();
+                detach_units(&mut outposts, from, player_action.tick_scheduled, units);
+                commands.spawn((id_service.new_object_id(), Army, army));
+            }
+            Err(failure) => failed_actions.actions.push((player_action, failure)),
+        }
+    }
+}
+
+/// Checks that the action could be executed if the game was in the state its curves hold on the tick it is scheduled for
+pub fn validate_player_action(
+    game_world: &mut World,
+    player_action: &PlayerAction,
+) -> Result<(), ActionFailure> {
+    let mut state = SystemState::<(OutpostCurves, ArmyCurves)>::new(game_world);
+    let (outposts, armies) = state.get_mut(game_world);
+    plan_army_action(&outposts, &armies, player_action).map(|_| ())
+}
+
+/// Works out the army an action sends out and the outpost it leaves from, without changing any curves
+pub(crate) fn plan_army_action(
+    outposts: &OutpostCurves,
+    armies: &ArmyCurves,
+    player_action: &PlayerAction,
+) -> Result<(ObjectId, NewArmyBundle), ActionFailure> {
+    match player_action.action {
+        Action::MoveArmy { from, .. } => Ok((from, move_army(outposts, player_action)?)),
+        Action::InterceptArmy { from, .. } => {
+            Ok((from, intercept_army(outposts, armies, player_action)?))
+        }
+    }
+}
+
+/// The curves of a newly sent out army
+#[derive(Bundle)]
+pub(crate) struct NewArmyBundle {
+    pub position: LinearCurve<ObjectPosition>,
+    pub general: SteppedCurve<ObjectGeneral>,
+    pub units: SteppedCurve<ArmyUnits>,
+    pub route: SteppedCurve<ArmyRoute>,
+}
+
+impl NewArmyBundle {
+    pub(crate) fn new(
+        tick: u64,
+        player: &AccountId,
+        units: UnitComposition,
+        position: LinearCurve<ObjectPosition>,
+        route: ArmyRoute,
+    ) -> NewArmyBundle {
+        let mut general_curve = SteppedCurve::<ObjectGeneral>::new();
+        general_curve.insert_keyframe(tick, ObjectGeneral::new(player.clone()));
+        let mut units_curve = SteppedCurve::<ArmyUnits>::new();
+        units_curve.insert_keyframe(tick, ArmyUnits { units });
+        let mut route_curve = SteppedCurve::<ArmyRoute>::new();
+        route_curve.insert_keyframe(tick, route);
+        NewArmyBundle {
+            position,
+            general: general_curve,
+            units: units_curve,
+            route: route_curve,
+        }
+    }
+}
+
+/// Returns the state of every outpost at `tick`
+pub(crate) fn outpost_snapshots(
+    outposts: &OutpostCurves,
+    tick: u64,
+) -> HashMap<ObjectId, OutpostSnapshot> {
+    outposts
+        .iter()
+        .filter_map(|(id, position, connections, general, _)| {
+            Some((
+                *id,
+                OutpostSnapshot {
+                    position: position.get_state(tick)?.position,
+                    connections: connections.get_state(tick)?.connections.clone(),
+                    owner: general
+                        .get_state(tick)
+                        .and_then(|general| general.general().cloned()),
+                },
+            ))
+        })
+        .collect()
+}
+
+/// Checks that `player` owns the `from` outpost at `tick` and that its garrison has at least `units` in it
+pub(crate) fn check_origin(
+    outposts: &OutpostCurves,
+    from: ObjectId,
+    tick: u64,
+    player: &AccountId,
+    units: &UnitComposition,
+) -> Result<(), ActionFailure> {
+    if units.is_empty() {
+        return Err(ActionFailure::NoUnits);
+    }
+    let Some((_, _, _, general, garrison)) = outposts.iter().find(|(id, _, _, _, _)| **id == from)
+    else {
+        return Err(ActionFailure::NotOutpostOwner);
+    };
+    if general
+        .get_state(tick)
+        .and_then(|general| general.general().cloned())
+        .as_ref()
+        != Some(player)
+    {
+        return Err(ActionFailure::NotOutpostOwner);
+    }
+    let garrison_units = garrison
+        .get_state(tick)
+        .map(|garrison| garrison.units)
+        .unwrap_or_default();
+    if garrison_units.checked_sub(units).is_none() {
+        return Err(ActionFailure::InsufficientUnits);
+    }
+    Ok(())
+}
+
+/// Removes `units` from the garrison of the `from` outpost at `tick`. Returns false and changes nothing if the garrison is
+/// too small
+pub(crate) fn detach_units(
+    outposts: &mut OutpostCurves,
+    from: ObjectId,
+    tick: u64,
+    units: &UnitComposition,
+) -> bool {
+    let Some((_, _, _, _, mut garrison)) =
+        outposts.iter_mut().find(|(id, _, _, _, _)| **id == from)
+    else {
+        return false;
+    };
+    let garrison_units = garrison
+        .get_state(tick)
+        .map(|garrison| garrison.units)
+        .unwrap_or_default();
+    let Some(remaining_units) = garrison_units.checked_sub(units) else {
+        return false;
+    };
+    if units.is_empty() {
+        return false;
+    }
+    insert_simulated_keyframe(
+        &mut garrison,
+        tick,
+        OutpostGarrison {
+            units: remaining_units,
+        },
+    );
+    true
+}
+
+/// Validates an [`Action::MoveArmy`] and returns the army it sends out
+fn move_army(
+    outposts: &OutpostCurves,
+    player_action: &PlayerAction,
+) -> Result<NewArmyBundle, ActionFailure> {
+    let Action::MoveArmy { from, to, units } = player_action.action else {
+        return Err(ActionFailure::NoRoute);
+    };
+    let tick = player_action.tick_scheduled;
+    let player = &player_action.issued_by_player;
+
+    check_origin(outposts, from, tick, player, &units)?;
+    let snapshots = outpost_snapshots(outposts, tick);
+    let origin = snapshots.get(&from).ok_or(ActionFailure::NotOutpostOwner)?;
+    if from == to {
+        return Err(ActionFailure::NoRoute);
+    }
+    let route = find_route(&snapshots, from, to, player).ok_or(ActionFailure::NoRoute)?;
+    let speed = units.speed().ok_or(ActionFailure::NoUnits)?;
+
+    let mut position_curve = LinearCurve::<ObjectPosition>::new();
+    let mut waypoints = vec![];
+    let mut arrival_tick = tick;
+    let mut last_position = origin.position;
+    for outpost in route.iter() {
+        let Some(snapshot) = snapshots.get(outpost) else {
+            continue;
+        };
+        if *outpost != from {
+            arrival_tick += travel_ticks(last_position, snapshot.position, speed);
+        }
+        position_curve.insert_keyframe(
+            arrival_tick,
+            ObjectPosition {
+                position: snapshot.position,
+            },
+        );
+        waypoints.push(ArmyWaypoint {
+            outpost: *outpost,
+            tick: arrival_tick,
+        });
+        last_position = snapshot.position;
+    }
+
+    Ok(NewArmyBundle::new(
+        tick,
+        player,
+        units,
+        position_curve,
+        ArmyRoute {
+            waypoints,
+            interception: None,
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{math::Vec2, utils::HashMap, utils::Uuid};
+
+    use crate::{
+        auth_server::AccountId,
+        game_simulation::army_movement::{find_route, OutpostSnapshot},
+        objects::core_components::ObjectId,
+    };
+
+    #[test]
+    fn test_route_avoids_enemy_outposts() {
+        let player = AccountId {
+            id: Uuid::from_u128(1),
+        };
+        let enemy = AccountId {
+            id: Uuid::from_u128(2),
+        };
+        let outsider = AccountId {
+            id: Uuid::from_u128(3),
+        };
+        // 0 - 1 - 3 is shorter than 0 - 2 - 3 but 1 is owned by an enemy
+        let mut outposts = HashMap::new();
+        for (id, position, connections, owner) in [
+            (0, Vec2::new(0.0, 0.0), vec![1, 2], Some(player.clone())),
+            (1, Vec2::new(10.0, 0.0), vec![0, 3], Some(enemy.clone())),
+            (2, Vec2::new(10.0, 20.0), vec![0, 3], Some(player.clone())),
+            (3, Vec2::new(20.0, 0.0), vec![1, 2], None),
+        ] {
+            outposts.insert(
+                ObjectId::new(id),
+                OutpostSnapshot {
+                    position,
+                    connections: connections.into_iter().map(ObjectId::new).collect(),
+                    owner,
+                },
+            );
+        }
+
+        let route = find_route(&outposts, ObjectId::new(0), ObjectId::new(3), &player).unwrap();
+        assert_eq!(
+            route,
+            vec![ObjectId::new(0), ObjectId::new(2), ObjectId::new(3)]
+        );
+
+        let route = find_route(&outposts, ObjectId::new(0), ObjectId::new(1), &player).unwrap();
+        assert_eq!(route, vec![ObjectId::new(0), ObjectId::new(1)]);
+
+        // Players who own nothing can only ever move one connection away
+        assert!(find_route(&outposts, ObjectId::new(0), ObjectId::new(3), &outsider).is_none());
+    }
+}
@@ -0,0 +1,302 @@
+//! Responsible for resolving what happens when armies arrive at their destination.
+//!
+//! Armies arriving at an outpost owned by the same player merge into its garrison. Armies arriving anywhere else fight the
+//! garrison using [`resolve_battle`], with the garrison getting the [`DefenceMultiplier`] of the outposts [`OutpostType`]. The
+//! result is written into the outposts [`ObjectGeneral`] and [`OutpostGarrison`] curves at the arrival tick. Battles only depend
+//! on curves that clients also have, so clients can forecast every battle ahead of time with [`resolve_battle`].
+//!
+//! Unit types counter each other. Infantry forms squares against cavalry, cavalry overruns artillery, and artillery breaks up
+//! infantry formations. Units fight [`COUNTER_ADVANTAGE`] as well against the type they counter and [`COUNTER_DISADVANTAGE`]
+//! as well against the type that counters them.
+
+use bevy::ecs::{
+    query::{With, Without},
+    system::{Query, Res},
+};
+use bevy_state_curves::prelude::{CurveTrait, SteppedCurve};
+
+use crate::{
+    auth_server::AccountId,
+    objects::{
+        army::{Army, ArmyRoute, ArmyUnits},
+        core_components::{ObjectGeneral, ObjectId},
+        outpost::{DefenceMultiplier, Outpost, OutpostGarrison, OutpostType},
+        units::{UnitComposition, UnitType},
+    },
+};
+
+use super::{insert_simulated_keyframe, SimulationTicks};
+
+/// How well units fight, in sixths, against the unit type they counter, an unrelated unit type, and the type that counters them.
+/// Kept as integers so that battles resolve identically on every machine
+pub const COUNTER_ADVANTAGE: u64 = 9;
+pub const COUNTER_NEUTRAL: u64 = 6;
+pub const COUNTER_DISADVANTAGE: u64 = 4;
+
+/// How well `unit_type` fights against `enemy_type`, in sixths
+pub fn counter_modifier(unit_type: UnitType, enemy_type: UnitType) -> u64 {
+    match (unit_type, enemy_type) {
+        (UnitType::Infantry, UnitType::Cavalry)
+        | (UnitType::Cavalry, UnitType::Artillery)
+        | (UnitType::Artillery, UnitType::Infantry) => COUNTER_ADVANTAGE,
+        (UnitType::Cavalry, UnitType::Infantry)
+        | (UnitType::Artillery, UnitType::Cavalry)
+        | (UnitType::Infantry, UnitType::Artillery) => COUNTER_DISADVANTAGE,
+        _ => COUNTER_NEUTRAL,
+    }
+}
+
+/// The strength of `units` when fighting `enemy`, with every unit fighting every enemy unit type in proportion to how many of
+/// them there are.
+///
+/// The strengths of both sides of a battle share the scale `6 * units.total() * enemy.total()` so they can be compared directly
+fn strength(units: &UnitComposition, enemy: &UnitComposition) -> u64 {
+    let mut strength = 0;
+    for unit_type in UnitType::ALL {
+        for enemy_type in UnitType::ALL {
+            strength += units.count(unit_type) as u64
+                * enemy.count(enemy_type) as u64
+                * counter_modifier(unit_type, enemy_type);
+        }
+    }
+    strength * units.total() as u64
+}
+
+/// The units the winner of a battle has left. Every unit type loses the same share of its units, the closer the fight the
+/// more is lost
+fn survivors(units: &UnitComposition, strength: u64, enemy_strength: u64) -> UnitComposition {
+    if enemy_strength == 0 {
+        return *units;
+    }
+    UnitComposition::from_counts(|unit_type| {
+        (units.count(unit_type) as u64 * (strength - enemy_strength) / strength) as u32
+    })
+}
+
+/// The outcome of a battle
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct BattleResult {
+    /// If the attackers took the outpost
+    pub attacker_won: bool,
+    /// The units the winning side has left
+    pub remaining_units: UnitComposition,
+}
+
+/// Resolves a battle between attacking units and the garrison defending an outpost. Defenders fight as if there were
+/// `defence_multiplier` times as many of them. Ties go to the defenders
+pub fn resolve_battle(
+    attackers: &UnitComposition,
+    defenders: &UnitComposition,
+    defence_multiplier: DefenceMultiplier,
+) -> BattleResult {
+    let attack = strength(attackers, defenders) * defence_multiplier.denominator as u64;
+    let defence = strength(defenders, attackers) * defence_multiplier.numerator as u64;
+    if defenders.is_empty() || attack > defence {
+        return BattleResult {
+            attacker_won: !attackers.is_empty(),
+            remaining_units: survivors(attackers, attack, defence),
+        };
+    }
+    BattleResult {
+        attacker_won: false,
+        remaining_units: survivors(defenders, defence, attack),
+    }
+}
+
+/// Resolves a battle between two armies that meet while travelling, where neither side gets a bonus. Returns the units each
+/// army has left, the loser is destroyed and ties destroy both
+pub fn resolve_field_battle(
+    first: &UnitComposition,
+    second: &UnitComposition,
+) -> (UnitComposition, UnitComposition) {
+    if first.is_empty() || second.is_empty() {
+        return (*first, *second);
+    }
+    let first_strength = strength(first, second);
+    let second_strength = strength(second, first);
+    match first_strength.cmp(&second_strength) {
+        std::cmp::Ordering::Greater => (
+            survivors(first, first_strength, second_strength),
+            UnitComposition::default(),
+        ),
+        std::cmp::Ordering::Less => (
+            UnitComposition::default(),
+            survivors(second, second_strength, first_strength),
+        ),
+        std::cmp::Ordering::Equal => (UnitComposition::default(), UnitComposition::default()),
+    }
+}
+
+struct Arrival {
+    tick: u64,
+    army: ObjectId,
+    player: AccountId,
+    units: UnitComposition,
+    outpost: ObjectId,
+}
+
+/// Resolves every army that arrives at its destination during the ticks being simulated. Arrived armies are left with no units
+#[allow(clippy::type_complexity)]
+pub fn resolve_army_arrivals(
+    ticks: Res<SimulationTicks>,
+    mut armies: Query<
+        (
+            &ObjectId,
+            &SteppedCurve<ArmyRoute>,
+            &SteppedCurve<ObjectGeneral>,
+            &mut SteppedCurve<ArmyUnits>,
+        ),
+        (With<Army>, Without<Outpost>),
+    >,
+    mut outposts: Query<
+        (
+            &ObjectId,
+            &mut SteppedCurve<ObjectGeneral>,
+            &mut SteppedCurve<OutpostGarrison>,
+            &SteppedCurve<OutpostType>,
+        ),
+        (With<Outpost>, Without<Army>),
+    >,
+) {
+    let simulated_ticks = ticks.last_simulated_tick + 1..=ticks.current_tick;
+    let mut arrivals: Vec<Arrival> = armies
+        .iter()
+        .filter_map(|(id, route, general, units)| {
+            let destination = *route.get_state(ticks.current_tick)?.destination()?;
+            if !simulated_ticks.contains(&destination.tick) {
+                return None;
+            }
+            Some(Arrival {
+                tick: destination.tick,
+                army: *id,
+                player: general.get_state(destination.tick)?.general()?.clone(),
+                units: units.get_state(destination.tick)?.units,
+                outpost: destination.outpost,
+            })
+        })
+        .filter(|arrival| !arrival.units.is_empty())
+        .collect();
+    // Armies arriving on the same tick are resolved in id order so every machine resolves them the same way
+    arrivals.sort_by_key(|arrival| (arrival.tick, arrival.army));
+
+    for arrival in arrivals {
+        let Some((_, mut general, mut garrison, outpost_type)) = outposts
+            .iter_mut()
+            .find(|(id, _, _, _)| **id == arrival.outpost)
+        else {
+            continue;
+        };
+        let owner = general
+            .get_state(arrival.tick)
+            .and_then(|general| general.general().cloned());
+        let garrison_units = garrison
+            .get_state(arrival.tick)
+            .map(|garrison| garrison.units)
+            .unwrap_or_default();
+
+        if owner.as_ref() == Some(&arrival.player) {
+            insert_simulated_keyframe(
+                &mut garrison,
+                arrival.tick,
+                OutpostGarrison {
+                    units: garrison_units + arrival.units,
+                },
+            );
+        } else {
+            let defence_multiplier = outpost_type
+                .get_state(arrival.tick)
+                .map(|outpost_type| outpost_type.defence_multiplier())
+                .unwrap_or_else(|| OutpostType::default().defence_multiplier());
+            let result = resolve_battle(&arrival.units, &garrison_units, defence_multiplier);
+            if result.attacker_won {
+                insert_simulated_keyframe(
+                    &mut general,
+                    arrival.tick,
+                    ObjectGeneral::new(arrival.player.clone()),
+                );
+            }
+            insert_simulated_keyframe(
+                &mut garrison,
+                arrival.tick,
+                OutpostGarrison {
+                    units: result.remaining_units,
+                },
+            );
+        }
+
+        if let Some((_, _, _, mut units)) =
+            armies.iter_mut().find(|(id, _, _, _)| **id == arrival.army)
+        {
+            units.insert_keyframe(arrival.tick, ArmyUnits::default());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        game_simulation::combat::{resolve_battle, resolve_field_battle, BattleResult},
+        objects::{outpost::DefenceMultiplier, units::UnitComposition},
+    };
+
+    fn units(infantry: u32, cavalry: u32, artillery: u32) -> UnitComposition {
+        UnitComposition {
+            infantry,
+            cavalry,
+            artillery,
+        }
+    }
+
+    #[test]
+    fn test_resolve_battle() {
+        let multiplier = DefenceMultiplier {
+            numerator: 3,
+            denominator: 2,
+        };
+        // 10 defenders fight like 15
+        assert_eq!(
+            resolve_battle(&units(20, 0, 0), &units(10, 0, 0), multiplier),
+            BattleResult {
+                attacker_won: true,
+                remaining_units: units(5, 0, 0)
+            }
+        );
+        assert_eq!(
+            resolve_battle(&units(15, 0, 0), &units(10, 0, 0), multiplier),
+            BattleResult {
+                attacker_won: false,
+                remaining_units: units(0, 0, 0)
+            }
+        );
+        assert_eq!(
+            resolve_battle(&units(1, 0, 0), &units(0, 0, 0), multiplier),
+            BattleResult {
+                attacker_won: true,
+                remaining_units: units(1, 0, 0)
+            }
+        );
+        // Artillery counters infantry so fewer of it can take the outpost
+        assert!(resolve_battle(&units(0, 0, 12), &units(10, 0, 0), multiplier).attacker_won);
+    }
+
+    #[test]
+    fn test_counters() {
+        // Every unit type beats the same amount of the type it counters and loses to the type that counters it
+        assert_eq!(
+            resolve_field_battle(&units(10, 0, 0), &units(0, 10, 0)).1,
+            units(0, 0, 0)
+        );
+        assert_eq!(
+            resolve_field_battle(&units(0, 10, 0), &units(0, 0, 10)).1,
+            units(0, 0, 0)
+        );
+        assert_eq!(
+            resolve_field_battle(&units(0, 0, 10), &units(10, 0, 0)).1,
+            units(0, 0, 0)
+        );
+        assert_eq!(
+            resolve_field_battle(&units(5, 5, 5), &units(5, 5, 5)),
+            (units(0, 0, 0), units(0, 0, 0))
+        );
+    }
+}
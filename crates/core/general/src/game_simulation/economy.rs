@@ -0,0 +1,102 @@
+//! Responsible for what outposts produce for the players that own them.
+//!
+//! Every [`PRODUCTION_INTERVAL`] ticks each owned outpost produces the [`OutpostProduction`] of its [`OutpostType`]. Resources are added
+//! to the owning players [`PlayerResources`] curve, which lives on an entity identified by the players [`AccountId`], and
+//! recruits are added to the outposts garrison. Neutral outposts produce nothing.
+
+use bevy::ecs::{
+    query::With,
+    system::{Query, Res},
+};
+use bevy_state_curves::prelude::{CurveTrait, SteppedCurve, SteppedKeyframe};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    auth_server::AccountId,
+    objects::{
+        core_components::ObjectGeneral,
+        outpost::{Outpost, OutpostGarrison, OutpostProduction, OutpostType},
+    },
+};
+
+use super::{insert_simulated_keyframe, SimulationTicks};
+
+/// How many ticks pass between every time outposts produce
+pub const PRODUCTION_INTERVAL: u64 = 10;
+
+/// The resources a player has stockpiled
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+pub struct PlayerResources {
+    pub resources: u32,
+}
+
+impl SteppedKeyframe<PlayerResources> for PlayerResources {}
+
+/// Produces resources and recruits on every production tick in the ticks being simulated
+#[allow(clippy::type_complexity)]
+pub fn produce_resources(
+    ticks: Res<SimulationTicks>,
+    mut outposts: Query<
+        (
+            &SteppedCurve<ObjectGeneral>,
+            &SteppedCurve<OutpostType>,
+            &mut SteppedCurve<OutpostGarrison>,
+        ),
+        With<Outpost>,
+    >,
+    mut players: Query<(&AccountId, &mut SteppedCurve<PlayerResources>)>,
+) {
+    let production_ticks = (ticks.last_simulated_tick + 1..=ticks.current_tick)
+        .filter(|tick| tick % PRODUCTION_INTERVAL == 0);
+
+    for tick in production_ticks {
+        for (general, outpost_type, mut garrison) in outposts.iter_mut() {
+            let Some(owner) = general
+                .get_state(tick)
+                .and_then(|general| general.general().cloned())
+            else {
+                continue;
+            };
+            let Some(OutpostProduction {
+                resources,
+                recruits,
+            }) = outpost_type
+                .get_state(tick)
+                .map(|outpost_type| outpost_type.production())
+            else {
+                continue;
+            };
+
+            if resources > 0 {
+                if let Some((_, mut stockpile)) =
+                    players.iter_mut().find(|(player, _)| **player == owner)
+                {
+                    let current = stockpile
+                        .get_state(tick)
+                        .map(|stockpile| stockpile.resources)
+                        .unwrap_or_default();
+                    insert_simulated_keyframe(
+                        &mut stockpile,
+                        tick,
+                        PlayerResources {
+                            resources: current + resources,
+                        },
+                    );
+                }
+            }
+            if !recruits.is_empty() {
+                let units = garrison
+                    .get_state(tick)
+                    .map(|garrison| garrison.units)
+                    .unwrap_or_default();
+                insert_simulated_keyframe(
+                    &mut garrison,
+                    tick,
+                    OutpostGarrison {
+                        units: units + recruits,
+                    },
+                );
+            }
+        }
+    }
+}
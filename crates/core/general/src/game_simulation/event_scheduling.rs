@@ -0,0 +1,87 @@
+//! Responsible for working out the next tick on which anything happens in a game world.
+//!
+//! Most ticks change nothing, so the server only runs the [`GameWorldSimulationSchedule`](super::GameWorldSimulationSchedule)
+//! once the [`NextInterestingTick`] of a game, or an earlier scheduled action, is reached. Every tick skipped over is then
+//! simulated in a single run. The interesting ticks are army arrivals, interceptions and production ticks while any outpost
+//! is owned.
+//!
+//! Production happens every few ticks in any started game and the ticks it missed are produced whenever the game is next
+//! simulated, so [`NextInterestingTick::event_tick`] leaves it out. That is the tick a game that is not in memory has to
+//! be loaded by.
+
+use bevy::ecs::{
+    query::With,
+    system::{Query, Res, ResMut, Resource},
+};
+use bevy_state_curves::prelude::{CurveTrait, SteppedCurve};
+
+use crate::objects::{
+    army::{Army, ArmyRoute, ArmyUnits},
+    core_components::ObjectGeneral,
+    outpost::Outpost,
+};
+
+use super::{economy::PRODUCTION_INTERVAL, SimulationTicks};
+
+/// The next tick after the last simulated tick on which something happens in the game world. None if nothing will ever
+/// happen without a new action.
+///
+/// Starts at tick 0 so that new and loaded games are simulated right away and work out their own next tick. Anything that
+/// changes a game world outside of its simulation must reset it
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct NextInterestingTick {
+    pub tick: Option<u64>,
+    /// The next tick after the last simulated tick on which an army arrives or is intercepted. None if no army is moving
+    pub event_tick: Option<u64>,
+}
+
+impl Default for NextInterestingTick {
+    fn default() -> Self {
+        NextInterestingTick {
+            tick: Some(0),
+            event_tick: Some(0),
+        }
+    }
+}
+
+/// Returns the first production tick after `tick`
+pub fn next_production_tick(tick: u64) -> u64 {
+    (tick / PRODUCTION_INTERVAL + 1) * PRODUCTION_INTERVAL
+}
+
+/// Finds the next interesting tick after the ticks that were just simulated
+pub fn update_next_interesting_tick(
+    ticks: Res<SimulationTicks>,
+    mut next_interesting_tick: ResMut<NextInterestingTick>,
+    armies: Query<(&SteppedCurve<ArmyRoute>, &SteppedCurve<ArmyUnits>), With<Army>>,
+    outposts: Query<&SteppedCurve<ObjectGeneral>, With<Outpost>>,
+) {
+    let tick = ticks.current_tick;
+    let army_event = armies
+        .iter()
+        .filter(|(_, units)| {
+            units
+                .get_state(tick)
+                .is_some_and(|units| !units.units.is_empty())
+        })
+        .filter_map(|(route, _)| route.get_state(tick))
+        .flat_map(|route| {
+            [
+                route.destination().map(|destination| destination.tick),
+                route.interception.map(|interception| interception.tick),
+            ]
+        })
+        .flatten()
+        .filter(|event_tick| *event_tick > tick)
+        .min();
+    // Neutral outposts produce nothing, and outposts only change owner on the ticks armies arrive
+    let any_owned_outposts = outposts.iter().any(|general| {
+        general
+            .get_state(tick)
+            .is_some_and(|general| general.general().is_some())
+    });
+    let production_event = any_owned_outposts.then(|| next_production_tick(tick));
+
+    next_interesting_tick.tick = army_event.into_iter().chain(production_event).min();
+    next_interesting_tick.event_tick = army_event;
+}
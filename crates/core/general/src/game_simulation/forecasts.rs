@@ -0,0 +1,91 @@
+//! Responsible for forecasting what queued actions will do once their tick arrives.
+//!
+//! Queued actions only change the curves once their tick is simulated. Until then every queued action is forecast against
+//! the curves on the tick it is scheduled for, in the order the actions will be executed in, with the units that earlier
+//! actions send out taken out of the garrisons they leave from. Cancelling or editing an action changes what the actions
+//! after it can do, so the server forecasts every queued action again whenever the queue changes and after every run of
+//! the simulation.
+//!
+//! Production and battles before an actions tick are not forecast, so a forecast can still turn out wrong once the action
+//! is executed. Those failures end up in [`FailedActions`](super::FailedActions)
+
+use bevy::{
+    ecs::{
+        system::{Resource, SystemState},
+        world::World,
+    },
+    utils::HashMap,
+};
+use bevy_state_curves::prelude::CurveTrait;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    actions::{ActionFailure, ActionId, PlayerAction},
+    auth_server::AccountId,
+    objects::{army::ArmyRoute, core_components::ObjectId, units::UnitComposition},
+};
+
+use super::army_movement::{plan_army_action, ArmyCurves, OutpostCurves};
+
+/// What a single queued action is forecast to do, the route of the army it sends out or why it will fail
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ActionForecast {
+    pub action_id: ActionId,
+    pub issued_by_player: AccountId,
+    pub forecast: Result<ArmyRoute, ActionFailure>,
+}
+
+/// The forecast of every queued action in a game
+#[derive(Resource, Clone, Debug, Default)]
+pub struct ActionForecasts {
+    pub forecasts: Vec<ActionForecast>,
+}
+
+impl ActionForecasts {
+    /// Returns the forecasts of every action issued by `player`
+    pub fn player_forecasts(&self, player: &AccountId) -> Vec<ActionForecast> {
+        self.forecasts
+            .iter()
+            .filter(|forecast| forecast.issued_by_player == *player)
+            .cloned()
+            .collect()
+    }
+}
+
+/// Forecasts every queued action and replaces the game worlds [`ActionForecasts`] with them
+pub fn forecast_actions(game_world: &mut World, queued_actions: &[PlayerAction]) {
+    let mut actions: Vec<&PlayerAction> = queued_actions.iter().collect();
+    // Due actions are executed in the order of their ticks, and in the order they were queued for the same tick
+    actions.sort_by_key(|action| action.tick_scheduled);
+
+    let mut state = SystemState::<(OutpostCurves, ArmyCurves)>::new(game_world);
+    let (outposts, armies) = state.get_mut(game_world);
+    let mut sent_units: HashMap<ObjectId, UnitComposition> = HashMap::new();
+    let mut forecasts = vec![];
+    for player_action in actions {
+        let tick = player_action.tick_scheduled;
+        let forecast =
+            plan_army_action(&outposts, &armies, player_action).and_then(|(from, army)| {
+                let sent = sent_units.get(&from).copied().unwrap_or_default()
+                    + *player_action.action.units();
+                let garrison_units = outposts
+                    .iter()
+                    .find(|(id, _, _, _, _)| **id == from)
+                    .and_then(|(_, _, _, _, garrison)| garrison.get_state(tick))
+                    .map(|garrison| garrison.units)
+                    .unwrap_or_default();
+                if garrison_units.checked_sub(&sent).is_none() {
+                    return Err(ActionFailure::InsufficientUnits);
+                }
+                sent_units.insert(from, sent);
+                army.route.get_state(tick).ok_or(ActionFailure::NoRoute)
+            });
+        forecasts.push(ActionForecast {
+            action_id: player_action.action_id,
+            issued_by_player: player_action.issued_by_player.clone(),
+            forecast,
+        });
+    }
+
+    game_world.insert_resource(ActionForecasts { forecasts });
+}
@@ -0,0 +1,235 @@
+//! Responsible for armies intercepting other armies while they travel.
+//!
+//! An [`Action::InterceptArmy`] sends a new army straight towards an enemy army. Where and when they meet is found from the
+//! targets position curve when the action is executed, and is stored in the interceptors [`ArmyRoute`] so clients know it
+//! ahead of time. The battle is resolved with [`resolve_field_battle`] once the meeting tick is simulated, and a surviving
+//! interceptor returns to the outpost it left from.
+
+use bevy::{
+    ecs::{
+        query::With,
+        system::{Query, Res},
+    },
+    math::Vec2,
+};
+use bevy_state_curves::prelude::{CurveTrait, LinearCurve, SteppedCurve};
+
+use crate::{
+    actions::{Action, ActionFailure, PlayerAction},
+    objects::{
+        army::{Army, ArmyInterception, ArmyRoute, ArmyUnits, ArmyWaypoint},
+        core_components::{ObjectGeneral, ObjectId, ObjectPosition},
+    },
+};
+
+use super::{
+    army_movement::{
+        check_origin, outpost_snapshots, travel_ticks, ArmyCurves, NewArmyBundle, OutpostCurves,
+    },
+    combat::resolve_field_battle,
+    SimulationTicks,
+};
+
+/// Finds the first tick after `departure_tick` and before `before_tick` on which an army travelling at `speed` and leaving
+/// `origin` on `departure_tick` can reach a target whose position on every tick is given by `target_position`.
+///
+/// Targets can be faster than the interceptor, so a target that can be reached on one tick may have escaped by the next.
+/// Every tick of the targets remaining route is checked
+pub fn interception_tick(
+    origin: Vec2,
+    speed: f32,
+    departure_tick: u64,
+    before_tick: u64,
+    target_position: impl Fn(u64) -> Option<Vec2>,
+) -> Option<u64> {
+    (departure_tick + 1..before_tick).find(|tick| {
+        target_position(*tick).is_some_and(|position| {
+            origin.distance(position) <= speed * (tick - departure_tick) as f32
+        })
+    })
+}
+
+/// Validates an [`Action::InterceptArmy`] and returns the army it sends out
+pub(crate) fn intercept_army(
+    outposts: &OutpostCurves,
+    armies: &ArmyCurves,
+    player_action: &PlayerAction,
+) -> Result<NewArmyBundle, ActionFailure> {
+    let Action::InterceptArmy {
+        from,
+        target,
+        units,
+    } = player_action.action
+    else {
+        return Err(ActionFailure::InvalidTarget);
+    };
+    let tick = player_action.tick_scheduled;
+    let player = &player_action.issued_by_player;
+
+    check_origin(outposts, from, tick, player, &units)?;
+    let origin = outpost_snapshots(outposts, tick)
+        .remove(&from)
+        .ok_or(ActionFailure::NotOutpostOwner)?;
+
+    let (_, target_position, target_general, target_units, target_route) = armies
+        .iter()
+        .find(|(id, _, _, _, _)| **id == target)
+        .ok_or(ActionFailure::InvalidTarget)?;
+    let target_owner = target_general
+        .get_state(tick)
+        .and_then(|general| general.general().cloned());
+    let target_destroyed = target_units
+        .get_state(tick)
+        .is_none_or(|units| units.units.is_empty());
+    if target_owner.as_ref() == Some(player) || target_destroyed {
+        return Err(ActionFailure::InvalidTarget);
+    }
+    let speed = units.speed().ok_or(ActionFailure::NoUnits)?;
+    // Armies that have arrived are part of a garrison and can't be intercepted anymore
+    let target_arrival = target_route
+        .get_state(tick)
+        .and_then(|route| route.destination().map(|destination| destination.tick))
+        .ok_or(ActionFailure::InvalidTarget)?;
+    let meeting_tick = interception_tick(origin.position, speed, tick, target_arrival, |tick| {
+        target_position.get_state(tick).map(|state| state.position)
+    })
+    .ok_or(ActionFailure::InvalidTarget)?;
+    let meeting_position = target_position
+        .get_state(meeting_tick)
+        .ok_or(ActionFailure::InvalidTarget)?
+        .position;
+
+    let return_tick = meeting_tick + travel_ticks(meeting_position, origin.position, speed);
+    let mut position_curve = LinearCurve::<ObjectPosition>::new();
+    for (tick, position) in [
+        (tick, origin.position),
+        (meeting_tick, meeting_position),
+        (return_tick, origin.position),
+    ] {
+        position_curve.insert_keyframe(tick, ObjectPosition { position });
+    }
+
+    Ok(NewArmyBundle::new(
+        tick,
+        player,
+        units,
+        position_curve,
+        ArmyRoute {
+            waypoints: vec![
+                ArmyWaypoint {
+                    outpost: from,
+                    tick,
+                },
+                ArmyWaypoint {
+                    outpost: from,
+                    tick: return_tick,
+                },
+            ],
+            interception: Some(ArmyInterception {
+                target,
+                tick: meeting_tick,
+            }),
+        },
+    ))
+}
+
+/// Resolves every interception that happens during the ticks being simulated. Interceptions whose target was already
+/// destroyed or has arrived miss, and the interceptor just returns home
+#[allow(clippy::type_complexity)]
+pub fn resolve_interceptions(
+    ticks: Res<SimulationTicks>,
+    mut armies: Query<
+        (
+            &ObjectId,
+            &SteppedCurve<ArmyRoute>,
+            &SteppedCurve<ObjectGeneral>,
+            &mut SteppedCurve<ArmyUnits>,
+        ),
+        With<Army>,
+    >,
+) {
+    let simulated_ticks = ticks.last_simulated_tick + 1..=ticks.current_tick;
+    let mut interceptions: Vec<(ObjectId, ArmyInterception)> = armies
+        .iter()
+        .filter_map(|(id, route, _, _)| {
+            let interception = route.get_state(ticks.current_tick)?.interception?;
+            simulated_ticks
+                .contains(&interception.tick)
+                .then_some((*id, interception))
+        })
+        .collect();
+    // Interceptions on the same tick are resolved in id order so every machine resolves them the same way
+    interceptions.sort_by_key(|(id, interception)| (interception.tick, *id));
+
+    for (interceptor, interception) in interceptions {
+        let state = |id: ObjectId| {
+            let (_, route, general, units) =
+                armies.iter().find(|(army_id, _, _, _)| **army_id == id)?;
+            Some((
+                general.get_state(interception.tick)?.general().cloned(),
+                units.get_state(interception.tick)?.units,
+                route.get_state(interception.tick)?.destination()?.tick,
+            ))
+        };
+        let (
+            Some((interceptor_owner, interceptor_units, _)),
+            Some((target_owner, target_units, target_arrival)),
+        ) = (state(interceptor), state(interception.target))
+        else {
+            continue;
+        };
+        if interceptor_owner == target_owner
+            || interceptor_units.is_empty()
+            || target_units.is_empty()
+            || target_arrival <= interception.tick
+        {
+            continue;
+        }
+
+        let (interceptor_remaining, target_remaining) =
+            resolve_field_battle(&interceptor_units, &target_units);
+        for (id, units) in [
+            (interceptor, interceptor_remaining),
+            (interception.target, target_remaining),
+        ] {
+            if let Some((_, _, _, mut units_curve)) =
+                armies.iter_mut().find(|(army_id, _, _, _)| **army_id == id)
+            {
+                units_curve.insert_keyframe(interception.tick, ArmyUnits { units });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::math::Vec2;
+
+    use crate::game_simulation::interception::interception_tick;
+
+    #[test]
+    fn test_interception_tick() {
+        // The target travels from (100, 0) on tick 0 to (0, 0) on tick 10
+        let target_position = |tick: u64| Some(Vec2::new(100.0 - 10.0 * tick.min(10) as f32, 0.0));
+        assert_eq!(
+            interception_tick(Vec2::ZERO, 10.0, 0, 10, target_position),
+            Some(5)
+        );
+        assert_eq!(
+            interception_tick(Vec2::ZERO, 10.0, 8, 10, target_position),
+            Some(9)
+        );
+        assert_eq!(
+            interception_tick(Vec2::ZERO, 10.0, 9, 10, target_position),
+            None
+        );
+
+        // A target travelling away just as fast can never be caught, but a faster interceptor catches it
+        let fleeing_position = |tick: u64| Some(Vec2::new(10.0 * tick as f32, 50.0));
+        assert_eq!(
+            interception_tick(Vec2::ZERO, 10.0, 0, 20, fleeing_position),
+            None
+        );
+        assert!(interception_tick(Vec2::ZERO, 15.0, 0, 20, fleeing_position).is_some());
+    }
+}
@@ -1,14 +1,90 @@
-use bevy::ecs::schedule::{Schedule, ScheduleLabel};
+//! Responsible for simulating game worlds. The server updates [`SimulationTicks`] and [`DueActions`] in a game world before
+//! running its [`GameWorldSimulationSchedule`]
+
+use bevy::ecs::{
+    schedule::{apply_deferred, ExecutorKind, IntoSystemConfigs, Schedule, ScheduleLabel},
+    system::Resource,
+};
+use bevy_state_curves::prelude::{CurveTrait, SteppedCurve, SteppedKeyframe};
+
+use crate::actions::{ActionFailure, PlayerAction};
+
+use self::{
+    army_movement::execute_army_actions, combat::resolve_army_arrivals, economy::produce_resources,
+    event_scheduling::update_next_interesting_tick, interception::resolve_interceptions,
+    visibility::update_visibility,
+};
+
+pub mod army_movement;
+pub mod combat;
+pub mod economy;
+pub mod event_scheduling;
+pub mod forecasts;
+pub mod interception;
+pub mod visibility;
 
 #[derive(ScheduleLabel, Hash, PartialEq, Eq, Debug, Clone)]
 pub struct GameWorldSimulationSchedule;
 
 impl GameWorldSimulationSchedule {
-    #[allow(clippy::let_and_return)]
     pub fn new_schedule() -> Schedule {
-        let schedule = Schedule::new(GameWorldSimulationSchedule);
-        //schedule.add_systems();
+        let mut schedule = Schedule::new(GameWorldSimulationSchedule);
+        // Games are simulated in parallel with each other, so each game runs on a single thread
+        schedule.set_executor_kind(ExecutorKind::SingleThreaded);
+        // Interceptions always happen before the target arrives, and armies arrive and outposts produce before new actions are
+        // executed so that arriving and recruited units can be sent out again on the same tick. Visibility is updated last, once
+        // the armies sent out this tick are spawned, followed by finding the next tick anything happens on
+        schedule.add_systems(
+            (
+                resolve_interceptions,
+                resolve_army_arrivals,
+                produce_resources,
+                execute_army_actions,
+                apply_deferred,
+                update_visibility,
+                update_next_interesting_tick,
+            )
+                .chain(),
+        );
 
         schedule
     }
 }
+
+/// The ticks that the current run of the simulation covers. Everything after `last_simulated_tick` up to and including
+/// `current_tick` is simulated
+#[derive(Resource, Clone, Copy, Debug, Default)]
+pub struct SimulationTicks {
+    pub last_simulated_tick: u64,
+    pub current_tick: u64,
+}
+
+/// Actions whose tick has arrived and that will be executed during the next run of the simulation
+#[derive(Resource, Default)]
+pub struct DueActions {
+    pub actions: Vec<PlayerAction>,
+}
+
+/// Actions that were due but could not be executed, along with why. Taken by the server to tell the players that issued them
+#[derive(Resource, Default)]
+pub struct FailedActions {
+    pub actions: Vec<(PlayerAction, ActionFailure)>,
+}
+
+/// Sets the state of a curve from `tick`, the tick being simulated.
+///
+/// A keyframe only holds the state it was given, so one inserted before a later keyframe would leave that later keyframe
+/// stale. The simulation only ever moves forward, simulating every tick in order, so a curve it changes never holds a
+/// keyframe after the tick being simulated
+pub(crate) fn insert_simulated_keyframe<T: SteppedKeyframe<T>>(
+    curve: &mut SteppedCurve<T>,
+    tick: u64,
+    state: T,
+) {
+    debug_assert!(
+        curve.next_keyframe(tick).is_none(),
+        "Simulated a keyframe on tick {} before a later keyframe",
+        tick
+    );
+    curve.insert_keyframe(tick, state);
+}
@@ -0,0 +1,452 @@
+//! Responsible for working out what every player can see.
+//!
+//! Players always see their own objects. Everything else is only visible while it is within the [`OutpostType::vision_range`]
+//! of an outpost they own or within [`ARMY_VISION_RANGE`] of one of their armies. The ticks an object is visible to a player
+//! are recorded as [`VisibleSegment`]s in [`PlayerVisibility`], which is used to decide which parts of curves can be shown to
+//! a player.
+//!
+//! Segments are only needed for the ticks players have not been sent yet, so [`VisibilitySet::forget_before`] drops older
+//! segments once they have been. The last segment of every object is always kept so players remember where they last saw
+//! it. Visibility is worked out from curves alone, so older segments can always be found again by simulating the curves.
+//!
+//! [`PlayerVisibility`] is saved with the rest of the game so it does not have to be worked out again when the game is loaded.
+
+use bevy::{
+    ecs::{
+        query::With,
+        system::{Query, Res, ResMut, Resource},
+    },
+    math::Vec2,
+    utils::HashMap,
+};
+use bevy_state_curves::prelude::{CurveTrait, LinearCurve, SteppedCurve};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    auth_server::AccountId,
+    objects::{
+        army::{Army, ArmyUnits},
+        core_components::{ObjectGeneral, ObjectId, ObjectPosition},
+        outpost::{Outpost, OutpostType},
+    },
+};
+
+use super::SimulationTicks;
+
+/// How far armies can see
+pub const ARMY_VISION_RANGE: f32 = 30.0;
+
+/// A range of ticks, including both `start` and `end`, during which an object is visible
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VisibleSegment {
+    pub start: u64,
+    pub end: u64,
+}
+
+impl VisibleSegment {
+    pub fn contains(&self, tick: u64) -> bool {
+        (self.start..=self.end).contains(&tick)
+    }
+}
+
+/// Every object a single player has seen and the ticks they saw it during
+#[derive(Clone, Debug, Default)]
+pub struct VisibilitySet {
+    pub objects: HashMap<ObjectId, Vec<VisibleSegment>>,
+}
+
+impl VisibilitySet {
+    /// Records that the object is visible on `tick`. Ticks must be recorded in order
+    pub fn insert(&mut self, object: ObjectId, tick: u64) {
+        let segments = self.objects.entry(object).or_default();
+        match segments.last_mut() {
+            Some(segment) if segment.end + 1 >= tick => segment.end = segment.end.max(tick),
+            _ => segments.push(VisibleSegment {
+                start: tick,
+                end: tick,
+            }),
+        }
+    }
+
+    /// Returns if the object was visible on `tick`
+    pub fn can_see(&self, object: &ObjectId, tick: u64) -> bool {
+        self.segments(object)
+            .iter()
+            .any(|segment| segment.contains(tick))
+    }
+
+    /// Returns every segment the object was visible during, oldest first
+    pub fn segments(&self, object: &ObjectId) -> &[VisibleSegment] {
+        self.objects
+            .get(object)
+            .map(|segments| segments.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Forgets every segment that ended before `tick`, other than the last segment of each object
+    pub fn forget_before(&mut self, tick: u64) {
+        for segments in self.objects.values_mut() {
+            let Some(last_segment) = segments.pop() else {
+                continue;
+            };
+            segments.retain(|segment| segment.end >= tick);
+            segments.push(last_segment);
+        }
+    }
+
+    /// Returns every object that was visible on `tick`
+    pub fn visible_objects(&self, tick: u64) -> impl Iterator<Item = &ObjectId> {
+        self.objects.iter().filter_map(move |(object, segments)| {
+            segments
+                .iter()
+                .any(|segment| segment.contains(tick))
+                .then_some(object)
+        })
+    }
+}
+
+/// What every player in a game can see and has seen
+#[derive(Resource, Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(from = "SavedPlayerVisibility", into = "SavedPlayerVisibility")]
+pub struct PlayerVisibility {
+    pub players: HashMap<AccountId, VisibilitySet>,
+}
+
+/// [`PlayerVisibility`] as it is saved. Json only has string keys, so every map is saved as a list
+#[derive(Serialize, Deserialize)]
+struct SavedPlayerVisibility {
+    players: Vec<(AccountId, SavedVisibilitySet)>,
+}
+
+/// The segments of every object in a [`VisibilitySet`]
+type SavedVisibilitySet = Vec<(ObjectId, Vec<VisibleSegment>)>;
+
+impl From<SavedPlayerVisibility> for PlayerVisibility {
+    fn from(saved: SavedPlayerVisibility) -> Self {
+        PlayerVisibility {
+            players: saved
+                .players
+                .into_iter()
+                .map(|(player, objects)| {
+                    (
+                        player,
+                        VisibilitySet {
+                            objects: objects.into_iter().collect(),
+                        },
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<PlayerVisibility> for SavedPlayerVisibility {
+    fn from(visibility: PlayerVisibility) -> Self {
+        SavedPlayerVisibility {
+            players: visibility
+                .players
+                .into_iter()
+                .map(|(player, visibility)| (player, visibility.objects.into_iter().collect()))
+                .collect(),
+        }
+    }
+}
+
+impl PlayerVisibility {
+    /// Returns if `player` could see the object on `tick`
+    pub fn can_see(&self, player: &AccountId, object: &ObjectId, tick: u64) -> bool {
+        self.players
+            .get(player)
+            .is_some_and(|visibility| visibility.can_see(object, tick))
+    }
+}
+
+/// An object that could be seen and, if it is owned, how far its owner can see from it
+struct VisibleObject {
+    id: ObjectId,
+    position: Vec2,
+    owner: Option<AccountId>,
+    vision_range: f32,
+}
+
+/// Buckets owned objects into square cells as wide as the largest vision range, so that every object that can see a
+/// position is in the cell of that position or one of its eight neighbours
+struct VisionGrid<'a> {
+    cell_size: f32,
+    cells: HashMap<(i32, i32), Vec<&'a VisibleObject>>,
+}
+
+impl<'a> VisionGrid<'a> {
+    fn new(objects: &'a [VisibleObject]) -> VisionGrid<'a> {
+        let sources = objects.iter().filter(|object| object.owner.is_some());
+        let cell_size = sources
+            .clone()
+            .map(|object| object.vision_range)
+            .fold(1.0, f32::max);
+        let mut cells: HashMap<(i32, i32), Vec<&VisibleObject>> = HashMap::new();
+        for source in sources {
+            cells
+                .entry(Self::cell(cell_size, source.position))
+                .or_default()
+                .push(source);
+        }
+        VisionGrid { cell_size, cells }
+    }
+
+    fn cell(cell_size: f32, position: Vec2) -> (i32, i32) {
+        (
+            (position.x / cell_size).floor() as i32,
+            (position.y / cell_size).floor() as i32,
+        )
+    }
+
+    /// Returns every owned object that has `position` within its vision range
+    fn sources_seeing(&self, position: Vec2) -> impl Iterator<Item = &VisibleObject> {
+        let (x, y) = Self::cell(self.cell_size, position);
+        (x - 1..=x + 1)
+            .flat_map(move |x| (y - 1..=y + 1).map(move |y| (x, y)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .copied()
+            .filter(move |source| source.position.distance(position) <= source.vision_range)
+    }
+}
+
+/// Records what every player can see on each of the ticks being simulated
+#[allow(clippy::type_complexity)]
+pub fn update_visibility(
+    ticks: Res<SimulationTicks>,
+    mut visibility: ResMut<PlayerVisibility>,
+    players: Query<&AccountId>,
+    outposts: Query<
+        (
+            &ObjectId,
+            &SteppedCurve<ObjectPosition>,
+            &SteppedCurve<ObjectGeneral>,
+            &SteppedCurve<OutpostType>,
+        ),
+        With<Outpost>,
+    >,
+    armies: Query<
+        (
+            &ObjectId,
+            &LinearCurve<ObjectPosition>,
+            &SteppedCurve<ObjectGeneral>,
+            &SteppedCurve<ArmyUnits>,
+        ),
+        With<Army>,
+    >,
+) {
+    for tick in ticks.last_simulated_tick + 1..=ticks.current_tick {
+        let outpost_objects =
+            outposts
+                .iter()
+                .filter_map(|(id, position, general, outpost_type)| {
+                    Some(VisibleObject {
+                        id: *id,
+                        position: position.get_state(tick)?.position,
+                        owner: general.get_state(tick)?.general().cloned(),
+                        vision_range: outpost_type.get_state(tick)?.vision_range(),
+                    })
+                });
+        // Armies only exist from when they are sent out until they arrive or are destroyed
+        let army_objects = armies
+            .iter()
+            .filter(|(_, _, _, units)| {
+                units
+                    .get_state(tick)
+                    .is_some_and(|units| !units.units.is_empty())
+            })
+            .filter_map(|(id, position, general, _)| {
+                Some(VisibleObject {
+                    id: *id,
+                    position: position.get_state(tick)?.position,
+                    owner: general.get_state(tick)?.general().cloned(),
+                    vision_range: ARMY_VISION_RANGE,
+                })
+            });
+        let objects: Vec<VisibleObject> = outpost_objects.chain(army_objects).collect();
+        let grid = VisionGrid::new(&objects);
+
+        for player in players.iter() {
+            visibility.players.entry(player.clone()).or_default();
+        }
+        for object in objects.iter() {
+            let owner = object.owner.iter();
+            let spotters = grid
+                .sources_seeing(object.position)
+                .filter_map(|source| source.owner.as_ref());
+            for player in owner.chain(spotters) {
+                if let Some(player_visibility) = visibility.players.get_mut(player) {
+                    player_visibility.insert(object.id, tick);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{
+        ecs::{schedule::Schedule, world::World},
+        math::Vec2,
+        utils::Uuid,
+    };
+    use bevy_state_curves::prelude::{CurveTrait, LinearCurve, SteppedCurve};
+
+    use crate::{
+        auth_server::AccountId,
+        game_simulation::{
+            visibility::{update_visibility, PlayerVisibility, VisibilitySet, VisibleSegment},
+            SimulationTicks,
+        },
+        objects::{
+            army::{Army, ArmyUnits},
+            core_components::{ObjectGeneral, ObjectId, ObjectPosition},
+            outpost::{Outpost, OutpostType},
+            units::UnitComposition,
+        },
+    };
+
+    #[test]
+    fn test_visibility_segments() {
+        let mut visibility = VisibilitySet::default();
+        for tick in [1, 2, 3, 7, 8] {
+            visibility.insert(ObjectId::new(0), tick);
+        }
+        assert_eq!(
+            visibility.segments(&ObjectId::new(0)),
+            &[
+                VisibleSegment { start: 1, end: 3 },
+                VisibleSegment { start: 7, end: 8 }
+            ]
+        );
+        assert!(visibility.can_see(&ObjectId::new(0), 2));
+        assert!(!visibility.can_see(&ObjectId::new(0), 5));
+        assert!(!visibility.can_see(&ObjectId::new(1), 2));
+        assert_eq!(visibility.visible_objects(8).count(), 1);
+    }
+
+    #[test]
+    fn test_forget_before() {
+        let mut visibility = VisibilitySet::default();
+        for tick in [1, 2, 5, 6, 9, 12] {
+            visibility.insert(ObjectId::new(0), tick);
+        }
+        visibility.insert(ObjectId::new(1), 1);
+        visibility.forget_before(6);
+        assert_eq!(
+            visibility.segments(&ObjectId::new(0)),
+            &[
+                VisibleSegment { start: 5, end: 6 },
+                VisibleSegment { start: 9, end: 9 },
+                VisibleSegment { start: 12, end: 12 }
+            ]
+        );
+        // Players still remember where they last saw the object
+        assert_eq!(
+            visibility.segments(&ObjectId::new(1)),
+            &[VisibleSegment { start: 1, end: 1 }]
+        );
+    }
+
+    #[test]
+    fn test_saved_visibility() {
+        let player = AccountId {
+            id: Uuid::from_u128(1),
+        };
+        let mut visibility = PlayerVisibility::default();
+        let player_visibility = visibility.players.entry(player.clone()).or_default();
+        for tick in [1, 2, 5] {
+            player_visibility.insert(ObjectId::new(0), tick);
+        }
+
+        let saved = serde_json::to_string(&visibility).unwrap();
+        let loaded: PlayerVisibility = serde_json::from_str(&saved).unwrap();
+        assert_eq!(
+            loaded.players[&player].segments(&ObjectId::new(0)),
+            visibility.players[&player].segments(&ObjectId::new(0))
+        );
+    }
+
+    #[test]
+    fn test_update_visibility() {
+        let player = AccountId {
+            id: Uuid::from_u128(1),
+        };
+        let enemy = AccountId {
+            id: Uuid::from_u128(2),
+        };
+        let mut world = World::new();
+        world.spawn(player.clone());
+        world.spawn(enemy.clone());
+
+        // A depot sees 50 around it and an army sees 30
+        let mut position = SteppedCurve::new();
+        position.insert_keyframe(
+            0,
+            ObjectPosition {
+                position: Vec2::new(0.0, 0.0),
+            },
+        );
+        let mut general = SteppedCurve::new();
+        general.insert_keyframe(0, ObjectGeneral::new(player.clone()));
+        let mut outpost_type = SteppedCurve::new();
+        outpost_type.insert_keyframe(0, OutpostType::Depot);
+        world.spawn((Outpost, ObjectId::new(0), position, general, outpost_type));
+
+        // The enemy army moves 10 a tick from 105 away to 5 away from the outpost and back again
+        let mut position = LinearCurve::new();
+        for (tick, x) in [(0, 105.0), (10, 5.0), (20, 105.0)] {
+            position.insert_keyframe(
+                tick,
+                ObjectPosition {
+                    position: Vec2::new(x, 0.0),
+                },
+            );
+        }
+        let mut general = SteppedCurve::new();
+        general.insert_keyframe(0, ObjectGeneral::new(enemy.clone()));
+        let mut units = SteppedCurve::new();
+        units.insert_keyframe(
+            0,
+            ArmyUnits {
+                units: UnitComposition {
+                    infantry: 10,
+                    cavalry: 0,
+                    artillery: 0,
+                },
+            },
+        );
+        world.spawn((Army, ObjectId::new(1), position, general, units));
+
+        world.init_resource::<PlayerVisibility>();
+        world.insert_resource(SimulationTicks {
+            last_simulated_tick: 0,
+            current_tick: 20,
+        });
+        let mut schedule = Schedule::default();
+        schedule.add_systems(update_visibility);
+        schedule.run(&mut world);
+
+        let visibility = world.resource::<PlayerVisibility>();
+        let player_visibility = &visibility.players[&player];
+        let enemy_visibility = &visibility.players[&enemy];
+        assert_eq!(
+            player_visibility.segments(&ObjectId::new(0)),
+            &[VisibleSegment { start: 1, end: 20 }]
+        );
+        assert_eq!(
+            player_visibility.segments(&ObjectId::new(1)),
+            &[VisibleSegment { start: 6, end: 14 }]
+        );
+        assert_eq!(
+            enemy_visibility.segments(&ObjectId::new(0)),
+            &[VisibleSegment { start: 8, end: 12 }]
+        );
+        assert_eq!(
+            enemy_visibility.segments(&ObjectId::new(1)),
+            &[VisibleSegment { start: 1, end: 20 }]
+        );
+    }
+}
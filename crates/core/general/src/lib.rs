@@ -47,6 +47,49 @@ pub fn clone_async_sender<T: 'static + Send + Sync + Clone>(
         .cloned()
 }
 
+/// Inserts a new [`AsyncChannelSender`] of the given type into the game world whose messages are held until
+/// [`GameWorldChannels::forward_messages`] sends them on to the servers [`AsyncChannelSender`] of the same type.
+///
+/// Game worlds are run in parallel, so buffering their messages lets the server forward them in a fixed order. Returns None if
+/// the server world does not have an [`AsyncChannelSender`] of the given type
+pub fn insert_buffered_async_sender<T: 'static + Send>(
+    server_world: &World,
+    game_world: &mut World,
+) -> Option<()> {
+    let server_sender = server_world
+        .get_resource::<AsyncChannelSender<T>>()?
+        .sender_channel
+        .clone();
+    let (game_sender, game_receiver) = create_async_channel::<T>();
+    game_world.insert_resource(game_sender);
+    game_world
+        .get_resource_or_insert_with(GameWorldChannels::default)
+        .forwarders
+        .push(Box::new(move || {
+            if let Ok(receiver) = game_receiver.reciever_channel.try_lock() {
+                while let Ok(message) = receiver.try_recv() {
+                    let _ = server_sender.send(message);
+                }
+            }
+        }));
+    Some(())
+}
+
+/// Holds every buffered channel of a game world set up by [`insert_buffered_async_sender`]
+#[derive(Resource, Default)]
+pub struct GameWorldChannels {
+    forwarders: Vec<Box<dyn Fn() + Send + Sync>>,
+}
+
+impl GameWorldChannels {
+    /// Sends every message the game world queued since the last call on to the servers channels
+    pub fn forward_messages(&self) {
+        for forwarder in self.forwarders.iter() {
+            forwarder();
+        }
+    }
+}
+
 /// Creates an [`AsyncChannelSender`] and an [`AsyncChannelReceiver`] for the given type
 pub fn create_async_channel<T>() -> (AsyncChannelSender<T>, AsyncChannelReceiver<T>) {
     let (sender, reciever) = mpsc::channel::<T>();
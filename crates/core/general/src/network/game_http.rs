@@ -13,3 +13,9 @@ pub struct QuitGame {
     pub game_id: GameId,
     pub player_id: AccountId,
 }
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct StartGame {
+    pub game_id: GameId,
+    pub player_id: AccountId,
+}
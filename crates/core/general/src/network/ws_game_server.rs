@@ -1,7 +1,18 @@
 use bevy_eventwork::NetworkMessage;
+use bevy_state_curves::prelude::{LinearCurve, SteppedCurve};
 use serde::{Deserialize, Serialize};
 
-use crate::{auth_server::AccountId, game_meta::GameId};
+use crate::{
+    actions::{Action, ActionFailure, ActionId, PlayerAction},
+    auth_server::AccountId,
+    game_meta::{GameId, NewGameSettings},
+    game_simulation::{economy::PlayerResources, forecasts::ActionForecast},
+    objects::{
+        army::{ArmyRoute, ArmyUnits},
+        core_components::{ObjectGeneral, ObjectId, ObjectPosition},
+        outpost::{OutpostConnections, OutpostGarrison, OutpostType},
+    },
+};
 
 /// Client message sent from the client to the game server to connect to a specific game
 ///
@@ -29,3 +40,202 @@ pub struct ClientInitialConnect {
 impl NetworkMessage for ClientInitialConnect {
     const NAME: &'static str = "ClientInitialMessage";
 }
+
+/// Server message sent to a player connected to a game with the curves of every object they can see that changed since the
+/// last update they were sent.
+///
+/// Curves only contain the keyframes the player is allowed to see from the tick of the previous update onward, starting with the
+/// state on that tick. They replace every keyframe the client already has for the object from that tick onward
+#[derive(Serialize, Deserialize)]
+pub struct GameStateUpdate {
+    pub game_id: GameId,
+    /// The last tick the game has been simulated up to
+    pub tick: u64,
+    pub objects: Vec<ObjectCurves>,
+    /// The players own resources, if they changed
+    pub player_resources: Option<SteppedCurve<PlayerResources>>,
+    /// The forecast of every action the player has queued, if any forecast in the game changed
+    pub action_forecasts: Option<Vec<ActionForecast>>,
+}
+
+impl NetworkMessage for GameStateUpdate {
+    const NAME: &'static str = "GameStateUpdate";
+}
+
+/// Server message sent to a player when they connect to a game with everything they need to build their local copy of it.
+///
+/// Contains every object the player can see with their visible curves. Every [`GameStateUpdate`] sent afterwards only
+/// contains what changed since this snapshot
+#[derive(Serialize, Deserialize)]
+pub struct GameStateSnapshot {
+    pub game_id: GameId,
+    /// The last tick the game has been simulated up to
+    pub tick: u64,
+    pub settings: NewGameSettings,
+    /// Every player that is playing in the game
+    pub players: Vec<AccountId>,
+    pub objects: Vec<ObjectCurves>,
+    /// The players own resources
+    pub player_resources: Option<SteppedCurve<PlayerResources>>,
+    /// The forecast of every action the player has queued
+    pub action_forecasts: Option<Vec<ActionForecast>>,
+}
+
+impl NetworkMessage for GameStateSnapshot {
+    const NAME: &'static str = "GameStateSnapshot";
+}
+
+/// The curves of a single object. Curves that the object does not have are None
+#[derive(Serialize, Deserialize)]
+pub struct ObjectCurves {
+    pub object_id: ObjectId,
+    pub object_general: Option<SteppedCurve<ObjectGeneral>>,
+    pub stepped_position: Option<SteppedCurve<ObjectPosition>>,
+    pub linear_position: Option<LinearCurve<ObjectPosition>>,
+    pub outpost_connections: Option<SteppedCurve<OutpostConnections>>,
+    pub outpost_garrison: Option<SteppedCurve<OutpostGarrison>>,
+    pub outpost_type: Option<SteppedCurve<OutpostType>>,
+    pub army_units: Option<SteppedCurve<ArmyUnits>>,
+    pub army_route: Option<SteppedCurve<ArmyRoute>>,
+}
+
+/// Client message sent to the game server to schedule an [`Action`] in the game the player is connected to.
+///
+/// The server answers with either a [`PlayerActionAccepted`] or a [`PlayerActionRejected`] carrying the same `request_id`
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SubmitPlayerAction {
+    /// Picked by the client to match the servers response to this request
+    pub request_id: u64,
+    pub game_id: GameId,
+    /// The tick the action should be executed on. Must be a tick the game has not reached yet
+    pub tick_scheduled: u64,
+    pub action: Action,
+}
+
+impl NetworkMessage for SubmitPlayerAction {
+    const NAME: &'static str = "SubmitPlayerAction";
+}
+
+/// Client message sent to the game server to cancel one of the players queued actions before it is executed.
+///
+/// The server answers with either a [`PlayerActionCancelled`] or a [`PlayerActionRejected`] carrying the same `request_id`
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CancelPlayerAction {
+    /// Picked by the client to match the servers response to this request
+    pub request_id: u64,
+    pub game_id: GameId,
+    pub action_id: ActionId,
+}
+
+impl NetworkMessage for CancelPlayerAction {
+    const NAME: &'static str = "CancelPlayerAction";
+}
+
+/// Client message sent to the game server to replace one of the players queued actions before it is executed. The action
+/// keeps its [`ActionId`].
+///
+/// The server answers with either a [`PlayerActionAccepted`] or a [`PlayerActionRejected`] carrying the same `request_id`
+#[derive(Serialize, Deserialize, Clone)]
+pub struct EditPlayerAction {
+    /// Picked by the client to match the servers response to this request
+    pub request_id: u64,
+    pub game_id: GameId,
+    pub action_id: ActionId,
+    /// The new tick the action should be executed on. Must be a tick the game has not reached yet
+    pub tick_scheduled: u64,
+    pub action: Action,
+}
+
+impl NetworkMessage for EditPlayerAction {
+    const NAME: &'static str = "EditPlayerAction";
+}
+
+/// Server message sent to a player when an action they submitted or edited was scheduled
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PlayerActionAccepted {
+    pub request_id: u64,
+    pub game_id: GameId,
+    /// The action as it was scheduled by the server
+    pub player_action: PlayerAction,
+}
+
+impl NetworkMessage for PlayerActionAccepted {
+    const NAME: &'static str = "PlayerActionAccepted";
+}
+
+/// Server message sent to a player when one of their actions was cancelled
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PlayerActionCancelled {
+    pub request_id: u64,
+    pub game_id: GameId,
+    pub action_id: ActionId,
+}
+
+impl NetworkMessage for PlayerActionCancelled {
+    const NAME: &'static str = "PlayerActionCancelled";
+}
+
+/// Server message sent to a player when one of their accepted actions could not be executed once its tick arrived
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PlayerActionFailed {
+    pub game_id: GameId,
+    pub action_id: ActionId,
+    pub reason: ActionFailure,
+}
+
+impl NetworkMessage for PlayerActionFailed {
+    const NAME: &'static str = "PlayerActionFailed";
+}
+
+/// Server message sent to a player when an action they submitted, cancelled or edited could not be changed
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PlayerActionRejected {
+    pub request_id: u64,
+    pub game_id: GameId,
+    pub reason: ActionRejectionReason,
+}
+
+impl NetworkMessage for PlayerActionRejected {
+    const NAME: &'static str = "PlayerActionRejected";
+}
+
+/// Why the server refused to schedule, cancel or edit an action
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ActionRejectionReason {
+    /// The connection has not sent a [`ClientInitialConnect`] yet
+    NotAuthenticated,
+    /// The player is not connected to the game the action was submitted for
+    NotConnectedToGame,
+    /// The game is not running on this server
+    GameNotFound,
+    /// The player is connected to the game but is not playing in it
+    NotAPlayerInGame,
+    /// The game is already past the tick the action was scheduled for
+    TickAlreadySimulated,
+    /// The action does not send any units
+    NoUnits,
+    /// The action being cancelled or edited is not queued. It either never existed or has already been executed
+    ActionNotQueued,
+    /// The action being cancelled or edited was issued by another player
+    NotActionOwner,
+    /// The player does not own the outpost the action sends units from
+    NotOutpostOwner,
+    /// The garrison of the outpost the action sends units from is too small
+    InsufficientUnits,
+    /// There is no route to the destination through outposts the player owns
+    NoRoute,
+    /// The army being intercepted does not exist, belongs to the player, has arrived or can not be reached in time
+    InvalidTarget,
+}
+
+impl From<ActionFailure> for ActionRejectionReason {
+    fn from(failure: ActionFailure) -> Self {
+        match failure {
+            ActionFailure::NoUnits => ActionRejectionReason::NoUnits,
+            ActionFailure::NotOutpostOwner => ActionRejectionReason::NotOutpostOwner,
+            ActionFailure::InsufficientUnits => ActionRejectionReason::InsufficientUnits,
+            ActionFailure::NoRoute => ActionRejectionReason::NoRoute,
+            ActionFailure::InvalidTarget => ActionRejectionReason::InvalidTarget,
+        }
+    }
+}
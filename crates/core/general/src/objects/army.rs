@@ -0,0 +1,48 @@
+use bevy::ecs::component::Component;
+use bevy_state_curves::prelude::SteppedKeyframe;
+use serde::{Deserialize, Serialize};
+
+use super::{core_components::ObjectId, units::UnitComposition};
+
+/// Marker component for objects that are armies
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Army;
+
+/// The units that make up an army
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+pub struct ArmyUnits {
+    pub units: UnitComposition,
+}
+
+impl SteppedKeyframe<ArmyUnits> for ArmyUnits {}
+
+/// The outposts an army travels through and the tick it arrives at each of them. The last waypoint is the armies destination
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct ArmyRoute {
+    pub waypoints: Vec<ArmyWaypoint>,
+    /// The army this army is sent to intercept on the way
+    #[serde(default)]
+    pub interception: Option<ArmyInterception>,
+}
+
+impl ArmyRoute {
+    /// Returns the final waypoint of the route
+    pub fn destination(&self) -> Option<&ArmyWaypoint> {
+        self.waypoints.last()
+    }
+}
+
+impl SteppedKeyframe<ArmyRoute> for ArmyRoute {}
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct ArmyWaypoint {
+    pub outpost: ObjectId,
+    pub tick: u64,
+}
+
+/// Where an army meets the army it was sent to intercept
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct ArmyInterception {
+    pub target: ObjectId,
+    pub tick: u64,
+}
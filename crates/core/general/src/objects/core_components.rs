@@ -1,12 +1,14 @@
 /// Core components every object will have
 use bevy::{ecs::component::Component, math::Vec2};
-use bevy_state_curves::prelude::SteppedKeyframe;
+use bevy_state_curves::prelude::{LinearKeyframe, SteppedKeyframe};
 use serde::{Deserialize, Serialize};
 
 use crate::auth_server::AccountId;
 
 /// An Id uniquely identifying an object in the game state
-#[derive(Component, Serialize, Deserialize, Debug)]
+#[derive(
+    Component, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug,
+)]
 pub struct ObjectId {
     pub id: u32,
 }
@@ -25,10 +27,33 @@ pub struct ObjectPosition {
 
 impl SteppedKeyframe<ObjectPosition> for ObjectPosition {}
 
+/// Moving objects use a linear curve so that their position at any tick between two keyframes can be found
+impl LinearKeyframe<ObjectPosition> for ObjectPosition {
+    fn lerp(&self, next: &ObjectPosition, ratio: f64) -> ObjectPosition {
+        ObjectPosition {
+            position: self.position.lerp(next.position, ratio as f32),
+        }
+    }
+}
+
 /// Component that holds what General (Player basically) controls this unit
 #[derive(Clone, Component, Serialize, Deserialize, Debug, Default)]
 pub struct ObjectGeneral {
     id: Option<AccountId>,
 }
 
+impl ObjectGeneral {
+    /// Creates a new [`ObjectGeneral`] controlled by the given player
+    pub fn new(player_id: AccountId) -> ObjectGeneral {
+        Self {
+            id: Some(player_id),
+        }
+    }
+
+    /// Returns the player controlling the object or None if it is neutral
+    pub fn general(&self) -> Option<&AccountId> {
+        self.id.as_ref()
+    }
+}
+
 impl SteppedKeyframe<ObjectGeneral> for ObjectGeneral {}
@@ -3,8 +3,10 @@ use serde::{Deserialize, Serialize};
 
 use self::core_components::ObjectId;
 
+pub mod army;
 pub mod core_components;
 pub mod outpost;
+pub mod units;
 
 /// A persistent service used to generate new unique ids. It is saved into the games meta db
 #[derive(Resource, Serialize, Deserialize, Clone, Debug)]
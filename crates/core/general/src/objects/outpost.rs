@@ -1,7 +1,111 @@
+use bevy::ecs::component::Component;
 use bevy_state_curves::prelude::SteppedKeyframe;
+use serde::{Deserialize, Serialize};
+
+use super::{core_components::ObjectId, units::UnitComposition};
+
+/// Marker component for objects that are outposts
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Outpost;
 
 /// The other outposts that connect to this outpost
-#[derive(Clone)]
-pub struct OutpostConnections {}
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+pub struct OutpostConnections {
+    pub connections: Vec<ObjectId>,
+}
 
 impl SteppedKeyframe<OutpostConnections> for OutpostConnections {}
+
+/// The units stationed in an outpost
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+pub struct OutpostGarrison {
+    pub units: UnitComposition,
+}
+
+impl SteppedKeyframe<OutpostGarrison> for OutpostGarrison {}
+
+/// The archetype of an outpost. Decides how well the outpost defends, what it produces and how far it sees. Stored as a curve so
+/// that captures or upgrades can change it over time
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+pub enum OutpostType {
+    /// Hard to take and sees far, but only slowly recruits artillery
+    Fortress,
+    /// Produces resources
+    #[default]
+    Depot,
+    /// Recruits infantry and cavalry
+    Factory,
+}
+
+impl SteppedKeyframe<OutpostType> for OutpostType {}
+
+impl OutpostType {
+    /// How much stronger the garrison of this outpost fights when defending
+    pub fn defence_multiplier(&self) -> DefenceMultiplier {
+        match self {
+            OutpostType::Fortress => DefenceMultiplier {
+                numerator: 2,
+                denominator: 1,
+            },
+            OutpostType::Depot => DefenceMultiplier {
+                numerator: 3,
+                denominator: 2,
+            },
+            OutpostType::Factory => DefenceMultiplier {
+                numerator: 5,
+                denominator: 4,
+            },
+        }
+    }
+
+    /// What this outpost produces for its owner every
+    /// [`PRODUCTION_INTERVAL`](crate::game_simulation::economy::PRODUCTION_INTERVAL)
+    pub fn production(&self) -> OutpostProduction {
+        match self {
+            OutpostType::Fortress => OutpostProduction {
+                resources: 0,
+                recruits: UnitComposition {
+                    infantry: 0,
+                    cavalry: 0,
+                    artillery: 1,
+                },
+            },
+            OutpostType::Depot => OutpostProduction {
+                resources: 5,
+                recruits: UnitComposition::default(),
+            },
+            OutpostType::Factory => OutpostProduction {
+                resources: 0,
+                recruits: UnitComposition {
+                    infantry: 1,
+                    cavalry: 1,
+                    artillery: 0,
+                },
+            },
+        }
+    }
+
+    /// How far the owner of this outpost can see from it
+    pub fn vision_range(&self) -> f32 {
+        match self {
+            OutpostType::Fortress => 80.0,
+            OutpostType::Depot | OutpostType::Factory => 50.0,
+        }
+    }
+}
+
+/// A multiplier stored as a ratio so that battles use integer math and resolve identically on every machine
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DefenceMultiplier {
+    pub numerator: u32,
+    pub denominator: u32,
+}
+
+/// What an outpost produces every production interval
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct OutpostProduction {
+    /// Added to the owning players stockpile
+    pub resources: u32,
+    /// Added to the outposts garrison
+    pub recruits: UnitComposition,
+}
@@ -0,0 +1,91 @@
+use std::ops::Add;
+
+use serde::{Deserialize, Serialize};
+
+/// The types of units that make up armies and garrisons
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq, Hash)]
+pub enum UnitType {
+    Infantry,
+    Cavalry,
+    Artillery,
+}
+
+impl UnitType {
+    pub const ALL: [UnitType; 3] = [UnitType::Infantry, UnitType::Cavalry, UnitType::Artillery];
+
+    /// How far this unit type travels every tick
+    pub fn speed(&self) -> f32 {
+        match self {
+            UnitType::Infantry => 10.0,
+            UnitType::Cavalry => 15.0,
+            UnitType::Artillery => 6.0,
+        }
+    }
+}
+
+/// How many of each [`UnitType`] are in an army or garrison
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+pub struct UnitComposition {
+    pub infantry: u32,
+    pub cavalry: u32,
+    pub artillery: u32,
+}
+
+impl UnitComposition {
+    /// Returns the amount of the given unit type
+    pub fn count(&self, unit_type: UnitType) -> u32 {
+        match unit_type {
+            UnitType::Infantry => self.infantry,
+            UnitType::Cavalry => self.cavalry,
+            UnitType::Artillery => self.artillery,
+        }
+    }
+
+    /// Returns a composition with every unit type set by `count`
+    pub fn from_counts(count: impl Fn(UnitType) -> u32) -> UnitComposition {
+        UnitComposition {
+            infantry: count(UnitType::Infantry),
+            cavalry: count(UnitType::Cavalry),
+            artillery: count(UnitType::Artillery),
+        }
+    }
+
+    /// The total amount of units of every type
+    pub fn total(&self) -> u32 {
+        self.infantry + self.cavalry + self.artillery
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.total() == 0
+    }
+
+    /// Removes `other` from this composition. Returns None if there are not enough units of any type
+    pub fn checked_sub(&self, other: &UnitComposition) -> Option<UnitComposition> {
+        Some(UnitComposition {
+            infantry: self.infantry.checked_sub(other.infantry)?,
+            cavalry: self.cavalry.checked_sub(other.cavalry)?,
+            artillery: self.artillery.checked_sub(other.artillery)?,
+        })
+    }
+
+    /// The speed of the slowest unit type in the composition. None if the composition is empty
+    pub fn speed(&self) -> Option<f32> {
+        UnitType::ALL
+            .into_iter()
+            .filter(|unit_type| self.count(*unit_type) > 0)
+            .map(|unit_type| unit_type.speed())
+            .min_by(|a, b| a.total_cmp(b))
+    }
+}
+
+impl Add for UnitComposition {
+    type Output = UnitComposition;
+
+    fn add(self, other: UnitComposition) -> UnitComposition {
+        UnitComposition {
+            infantry: self.infantry + other.infantry,
+            cavalry: self.cavalry + other.cavalry,
+            artillery: self.artillery + other.artillery,
+        }
+    }
+}
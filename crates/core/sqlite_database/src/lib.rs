@@ -1,5 +1,5 @@
 use database_traits::DatabaseData;
-use general::clone_async_sender;
+use general::insert_buffered_async_sender;
 use rusqlite::{params_from_iter, Error, Transaction};
 use saving::DatabaseSavePlugin;
 use schemes::{
@@ -18,6 +18,7 @@ use bevy::{
 use rusqlite::Connection;
 
 pub mod database_traits;
+pub mod loading;
 pub mod saving;
 pub mod schemes;
 pub mod update_row;
@@ -65,12 +66,11 @@ pub struct DatabaseDataRegistry {
     pub map: HashMap<&'static str, Box<dyn DatabaseData>>,
 }
 
-/// Fn that sets up the game world with everything needed by the save and database systems
+/// Fn that sets up the game world with everything needed by the save and database systems. Every channel is buffered in the
+/// game world, see [`insert_buffered_async_sender`]
 pub fn game_world_setup_saving(server_world: &World, game_world: &mut World) {
-    game_world.insert_resource(
-        clone_async_sender::<UpdateRow>(server_world)
-            .expect("AsyncChannelSender<UpdateRow> not found"),
-    );
+    insert_buffered_async_sender::<UpdateRow>(server_world, game_world)
+        .expect("AsyncChannelSender<UpdateRow> not found");
 
     setup_game_server_schemes(server_world, game_world)
 }
@@ -0,0 +1,249 @@
+//! Responsible for reading saved games back out of the database so they can be resumed.
+//!
+//! Objects and players are spawned with an [`ExistsInDatabase`] component so the save systems only update their rows
+
+use bevy::{ecs::world::World, log::error};
+use bevy_state_curves::prelude::{LinearCurve, SteppedCurve};
+use general::{
+    auth_server::AccountId,
+    game_meta::{GameId, GameSeed, NewGameSettings},
+    game_simulation::{economy::PlayerResources, visibility::PlayerVisibility},
+    objects::{
+        army::{Army, ArmyRoute, ArmyUnits},
+        core_components::{ObjectGeneral, ObjectId, ObjectPosition},
+        outpost::{Outpost, OutpostConnections, OutpostGarrison, OutpostType},
+        ObjectIdService,
+    },
+};
+use rusqlite::{types::ValueRef, Connection, Row};
+use serde::de::DeserializeOwned;
+
+use crate::saving::ExistsInDatabase;
+
+/// The meta information of a saved game needed to rebuild its game world
+pub struct SavedGame {
+    pub game_id: GameId,
+    pub object_id_service: ObjectIdService,
+    pub seed: GameSeed,
+    pub settings: NewGameSettings,
+    /// The tick the game had been simulated up to when it was saved
+    pub game_tick: u64,
+    /// The unix time in milliseconds that tick 0 of the game happened at, None for games saved before it was stored
+    pub started_at: Option<u64>,
+    /// The unix time in milliseconds the game has to be loaded by to simulate its next event, or None if nothing is
+    /// scheduled. Games that were in memory when they were last saved have to be loaded straight away
+    pub wake_at: Option<u64>,
+    /// What every player could see when the game was saved, None for games saved before it was stored
+    pub player_visibility: Option<PlayerVisibility>,
+    /// The outposts players can be given as their home when the game starts. Empty if any outpost can be a home, which is
+    /// also the case for games saved before start slots were stored
+    pub start_slots: Vec<ObjectId>,
+}
+
+struct SavedGameRow {
+    game_id: String,
+    object_id_service: String,
+    seed: Option<String>,
+    max_players: Option<String>,
+    game_settings: Option<String>,
+    game_tick: Option<String>,
+    started_at: Option<String>,
+    wake_at: Option<String>,
+    player_visibility: Option<String>,
+    start_slots: Option<String>,
+}
+
+const SAVED_GAME_COLUMNS: &str = "game_id, object_id_service, seed, max_players, game_settings, game_tick, started_at, wake_at, player_visibility, start_slots";
+
+/// Reads every game in the games meta table. Games that can not be read are logged and skipped
+pub fn saved_games(connection: &Connection) -> Result<Vec<SavedGame>, rusqlite::Error> {
+    let mut stmt = connection.prepare(&format!("SELECT {} FROM games_meta", SAVED_GAME_COLUMNS))?;
+    let rows = stmt.query_map((), saved_game_row)?;
+    read_saved_games(rows)
+}
+
+/// Reads a single game from the games meta table, returns None if it does not exist or can not be read
+pub fn saved_game(
+    connection: &Connection,
+    game_id: &GameId,
+) -> Result<Option<SavedGame>, rusqlite::Error> {
+    let mut stmt = connection.prepare(&format!(
+        "SELECT {} FROM games_meta WHERE game_id = ?1",
+        SAVED_GAME_COLUMNS
+    ))?;
+    let rows = stmt.query_map([game_id.to_json()], saved_game_row)?;
+    Ok(read_saved_games(rows)?.pop())
+}
+
+fn saved_game_row(row: &Row) -> Result<SavedGameRow, rusqlite::Error> {
+    Ok(SavedGameRow {
+        game_id: row.get(0)?,
+        object_id_service: row.get(1)?,
+        seed: column_string(row, 2)?,
+        max_players: column_string(row, 3)?,
+        game_settings: row.get(4)?,
+        game_tick: column_string(row, 5)?,
+        started_at: column_string(row, 6)?,
+        wake_at: column_string(row, 7)?,
+        player_visibility: row.get(8)?,
+        start_slots: row.get(9)?,
+    })
+}
+
+fn read_saved_games(
+    rows: impl Iterator<Item = Result<SavedGameRow, rusqlite::Error>>,
+) -> Result<Vec<SavedGame>, rusqlite::Error> {
+    let mut games = vec![];
+    for row in rows {
+        let row = row?;
+        let (Some(game_id), Some(object_id_service)) = (
+            parse::<GameId>(Some(row.game_id.clone())),
+            parse::<ObjectIdService>(Some(row.object_id_service)),
+        ) else {
+            error!("Skipping saved game {} that could not be read", row.game_id);
+            continue;
+        };
+        // Games saved before their settings were stored only kept their player count
+        let settings = parse::<NewGameSettings>(row.game_settings).unwrap_or_else(|| {
+            let mut settings = NewGameSettings::default();
+            if let Some(max_players) = row.max_players.and_then(|players| players.parse().ok()) {
+                settings.max_player_count = max_players;
+            }
+            settings
+        });
+        games.push(SavedGame {
+            game_id,
+            object_id_service,
+            seed: GameSeed {
+                seed: row
+                    .seed
+                    .and_then(|seed| seed.parse().ok())
+                    .unwrap_or_default(),
+            },
+            settings,
+            game_tick: row
+                .game_tick
+                .and_then(|tick| tick.parse().ok())
+                .unwrap_or_default(),
+            started_at: row
+                .started_at
+                .and_then(|started_at| started_at.parse().ok()),
+            // Games saved before they could be unloaded never had a wake time
+            wake_at: parse::<Option<u64>>(row.wake_at).unwrap_or(Some(0)),
+            player_visibility: parse::<PlayerVisibility>(row.player_visibility),
+            start_slots: parse::<Vec<ObjectId>>(row.start_slots).unwrap_or_default(),
+        });
+    }
+    Ok(games)
+}
+
+struct GameCurvesRow {
+    object_id: String,
+    object_general: String,
+    stepped_position: Option<String>,
+    linear_position: Option<String>,
+    outpost_connections: Option<String>,
+    outpost_garrison: Option<String>,
+    army_units: Option<String>,
+    army_route: Option<String>,
+    outpost_type: Option<String>,
+}
+
+/// Spawns every object in the games curves table and every player in its players table into the game world
+pub fn load_game_objects(
+    connection: &Connection,
+    game_world: &mut World,
+    game_id: &GameId,
+) -> Result<(), rusqlite::Error> {
+    let mut stmt = connection.prepare(&format!(
+        "SELECT object_id, sc_object_general, sc_object_position, lc_object_position, sc_outpost_connections, sc_outpost_garrison, sc_army_units, sc_army_route, sc_outpost_type FROM \"game_curves_{}\"",
+        game_id.id_as_string()
+    ))?;
+    let rows = stmt.query_map((), |row| {
+        Ok(GameCurvesRow {
+            object_id: row.get(0)?,
+            object_general: row.get(1)?,
+            stepped_position: row.get(2)?,
+            linear_position: row.get(3)?,
+            outpost_connections: row.get(4)?,
+            outpost_garrison: row.get(5)?,
+            army_units: row.get(6)?,
+            army_route: row.get(7)?,
+            outpost_type: row.get(8)?,
+        })
+    })?;
+
+    for row in rows {
+        let row = row?;
+        let (Some(object_id), Some(general)) = (
+            parse::<ObjectId>(Some(row.object_id.clone())),
+            parse::<SteppedCurve<ObjectGeneral>>(Some(row.object_general)),
+        ) else {
+            error!(
+                "Skipping saved object {} that could not be read",
+                row.object_id
+            );
+            continue;
+        };
+        let mut entity = game_world.spawn((object_id, general, ExistsInDatabase));
+        // Outposts are the only objects with a stepped position and armies the only ones with a linear position
+        if let Some(position) = parse::<SteppedCurve<ObjectPosition>>(row.stepped_position) {
+            entity.insert((position, Outpost));
+        }
+        if let Some(position) = parse::<LinearCurve<ObjectPosition>>(row.linear_position) {
+            entity.insert((position, Army));
+        }
+        if let Some(connections) =
+            parse::<SteppedCurve<OutpostConnections>>(row.outpost_connections)
+        {
+            entity.insert(connections);
+        }
+        if let Some(garrison) = parse::<SteppedCurve<OutpostGarrison>>(row.outpost_garrison) {
+            entity.insert(garrison);
+        }
+        if let Some(outpost_type) = parse::<SteppedCurve<OutpostType>>(row.outpost_type) {
+            entity.insert(outpost_type);
+        }
+        if let Some(units) = parse::<SteppedCurve<ArmyUnits>>(row.army_units) {
+            entity.insert(units);
+        }
+        if let Some(route) = parse::<SteppedCurve<ArmyRoute>>(row.army_route) {
+            entity.insert(route);
+        }
+    }
+
+    let mut stmt = connection.prepare(&format!(
+        "SELECT account_id, sc_player_resources FROM \"game_players_{}\"",
+        game_id.id_as_string()
+    ))?;
+    let rows = stmt.query_map((), |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?))
+    })?;
+    for row in rows {
+        let (account_id, resources) = row?;
+        let Some(account_id) = parse::<AccountId>(Some(account_id)) else {
+            continue;
+        };
+        let resources = parse::<SteppedCurve<PlayerResources>>(resources).unwrap_or_default();
+        game_world.spawn((account_id, resources, ExistsInDatabase));
+    }
+
+    Ok(())
+}
+
+/// Reads a column that was saved as a number as a string, whether sqlite stored it as text or as a number
+fn column_string(row: &Row, index: usize) -> Result<Option<String>, rusqlite::Error> {
+    Ok(match row.get_ref(index)? {
+        ValueRef::Null => None,
+        ValueRef::Integer(value) => Some(value.to_string()),
+        ValueRef::Real(value) => Some(value.to_string()),
+        ValueRef::Text(value) | ValueRef::Blob(value) => {
+            Some(String::from_utf8_lossy(value).to_string())
+        }
+    })
+}
+
+/// Deserializes a column saved as json
+fn parse<T: DeserializeOwned>(data: Option<String>) -> Option<T> {
+    serde_json::from_str(&data?).ok()
+}
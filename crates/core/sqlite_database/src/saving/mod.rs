@@ -1,27 +1,41 @@
 //! Responsible for automatically saving changed data into the database. Will send an [`AsyncChannelSender`] message for each component that changes.
 //!
-//! Note that this does not Insert new rows and that this runs in the game world and not the server world
+//! New objects and players that do not have an [`ExistsInDatabase`] component yet are inserted as new rows. The games
+//! [`ObjectIdService`], the tick it has been simulated up to and its [`PlayerVisibility`] are saved into its games meta
+//! row. Note that this runs in the game world and not the server world
 
 use bevy::{
     app::{App, Plugin},
     ecs::{
+        change_detection::DetectChanges,
         component::Component,
-        query::With,
+        entity::Entity,
+        query::{With, Without},
         removal_detection::RemovedComponents,
-        schedule::{Schedule, ScheduleLabel},
+        schedule::{ExecutorKind, Schedule, ScheduleLabel},
         system::{Commands, Query, Res, Resource},
     },
 };
-use bevy_state_curves::prelude::SteppedCurve;
+use bevy_state_curves::prelude::{LinearCurve, SteppedCurve};
 use general::{
+    auth_server::AccountId,
     game_meta::GameId,
-    objects::core_components::{ObjectGeneral, ObjectId, ObjectPosition},
+    game_simulation::{economy::PlayerResources, visibility::PlayerVisibility, SimulationTicks},
+    objects::{
+        army::{ArmyRoute, ArmyUnits},
+        core_components::{ObjectGeneral, ObjectId, ObjectPosition},
+        outpost::{OutpostConnections, OutpostGarrison, OutpostType},
+        ObjectIdService,
+    },
     AsyncChannelSender,
 };
 
 use crate::{
-    database_traits::{DatabaseData, GameDatabaseTable},
-    schemes::game_server::GameCurvesTable,
+    database_traits::{DatabaseData, DatabaseTable, GameDatabaseTable, PureDatabaseData},
+    schemes::game_server::{
+        game_tables::{InsertGameCurvesRow, InsertGamePlayersRow},
+        GameCurvesTable, GamesMetaTable, GamesPlayersTable,
+    },
     update_row::UpdateRow,
 };
 
@@ -40,9 +54,23 @@ impl SaveSchedule {
     /// Creates a new Schedule with all the neccesary save schedule systems
     pub fn new_schedule() -> Schedule {
         let mut schedule = Schedule::new(SaveSchedule);
+        // Games are saved in parallel with each other, so each game runs on a single thread
+        schedule.set_executor_kind(ExecutorKind::SingleThreaded);
         schedule.add_systems((
+            insert_new_objects,
+            insert_new_players,
             save_component::<GameCurvesTable, ObjectId, SteppedCurve<ObjectPosition>>,
-            save_component::<GameCurvesTable, ObjectId, ObjectGeneral>,
+            save_component::<GameCurvesTable, ObjectId, LinearCurve<ObjectPosition>>,
+            save_component::<GameCurvesTable, ObjectId, SteppedCurve<ObjectGeneral>>,
+            save_component::<GameCurvesTable, ObjectId, SteppedCurve<OutpostConnections>>,
+            save_component::<GameCurvesTable, ObjectId, SteppedCurve<OutpostGarrison>>,
+            save_component::<GameCurvesTable, ObjectId, SteppedCurve<ArmyUnits>>,
+            save_component::<GameCurvesTable, ObjectId, SteppedCurve<ArmyRoute>>,
+            save_component::<GameCurvesTable, ObjectId, SteppedCurve<OutpostType>>,
+            save_component::<GamesPlayersTable, AccountId, SteppedCurve<PlayerResources>>,
+            save_game_meta::<ObjectIdService>,
+            save_game_meta::<SimulationTicks>,
+            save_game_meta::<PlayerVisibility>,
         ));
 
         schedule
@@ -52,6 +80,108 @@ impl SaveSchedule {
 #[derive(Component)]
 pub struct ExistsInDatabase;
 
+/// Fn that sends an [`InsertGameCurvesRow`] message for every object that does not have an [`ExistsInDatabase`] component and
+/// then marks it as existing
+#[allow(clippy::type_complexity)]
+fn insert_new_objects(
+    mut commands: Commands,
+    query: Query<
+        (
+            Entity,
+            &ObjectId,
+            &SteppedCurve<ObjectGeneral>,
+            Option<&SteppedCurve<ObjectPosition>>,
+            Option<&LinearCurve<ObjectPosition>>,
+            Option<&SteppedCurve<OutpostConnections>>,
+            Option<&SteppedCurve<OutpostGarrison>>,
+            Option<&SteppedCurve<OutpostType>>,
+            Option<&SteppedCurve<ArmyUnits>>,
+            Option<&SteppedCurve<ArmyRoute>>,
+        ),
+        Without<ExistsInDatabase>,
+    >,
+    insert_row_channel: Res<AsyncChannelSender<InsertGameCurvesRow>>,
+    game_id: Res<GameId>,
+) {
+    for (
+        entity,
+        object_id,
+        general,
+        stepped_position,
+        linear_position,
+        connections,
+        garrison,
+        outpost_type,
+        army_units,
+        army_route,
+    ) in query.iter()
+    {
+        // Only objects that have the optional curves save them
+        let optional_data: Vec<PureDatabaseData> = [
+            connections.and_then(|curve| curve.to_database_data()),
+            garrison.and_then(|curve| curve.to_database_data()),
+            outpost_type.and_then(|curve| curve.to_database_data()),
+            army_units.and_then(|curve| curve.to_database_data()),
+            army_route.and_then(|curve| curve.to_database_data()),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        // Objects have either a stepped or a linear position
+        let row = match (stepped_position, linear_position) {
+            (Some(position), _) => {
+                InsertGameCurvesRow::new_row(*game_id, object_id, general, position)
+            }
+            (None, Some(position)) => {
+                InsertGameCurvesRow::new_row(*game_id, object_id, general, position)
+            }
+            (None, None) => None,
+        };
+        let Some(row) = row.and_then(|row| {
+            optional_data
+                .iter()
+                .try_fold(row, |row, data| row.with_data(data))
+        }) else {
+            continue;
+        };
+        let _ = insert_row_channel.sender_channel.send(row);
+        commands.entity(entity).insert(ExistsInDatabase);
+    }
+}
+
+/// Fn that sends an [`InsertGamePlayersRow`] message for every player that does not have an [`ExistsInDatabase`] component and
+/// then marks it as existing
+fn insert_new_players(
+    mut commands: Commands,
+    query: Query<(Entity, &AccountId, &SteppedCurve<PlayerResources>), Without<ExistsInDatabase>>,
+    insert_row_channel: Res<AsyncChannelSender<InsertGamePlayersRow>>,
+    game_id: Res<GameId>,
+) {
+    for (entity, account_id, resources) in query.iter() {
+        let Some(row) = InsertGamePlayersRow::new_row(*game_id, account_id, resources) else {
+            continue;
+        };
+        let _ = insert_row_channel.sender_channel.send(row);
+        commands.entity(entity).insert(ExistsInDatabase);
+    }
+}
+
+/// Fn that sends an [`UpdateRow`] message to save a game world resource into the games row of the games meta table whenever it
+/// changes
+fn save_game_meta<R: Resource + DatabaseData>(
+    resource: Res<R>,
+    update_row_channel: Res<AsyncChannelSender<UpdateRow>>,
+    table: Res<GamesMetaTable>,
+    game_id: Res<GameId>,
+) {
+    if !resource.is_changed() {
+        return;
+    }
+    if let Ok(update_row) = UpdateRow::new(table.table_name(), &*game_id, &*resource) {
+        let _ = update_row_channel.sender_channel.send(update_row);
+    }
+}
+
 /// Fn that sends an [`UpdateRow`] message for any component that has changed and has an [`ExistsInDatabase`] component. Note that when
 #[allow(clippy::type_complexity)]
 fn save_component<
@@ -1 +1,197 @@
+//! Schemas for the table of actions players have scheduled in a game.
+//!
+//! Every accepted [`PlayerAction`] is inserted as [`GameActionState::Queued`] and is only ever updated afterwards, so the
+//! table also keeps the history of every action that was cancelled or executed
 
+use bevy::{ecs::component::Component, log::error};
+use general::{
+    actions::{ActionId, PlayerAction},
+    game_meta::GameId,
+};
+use rusqlite::Connection;
+
+use crate::{
+    database_traits::{DatabaseData, DatabaseSql},
+    schemes::column_definitions,
+};
+
+/// The state of an action in the game actions table
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GameActionState {
+    /// Waiting for its tick to be simulated
+    Queued,
+    /// Cancelled by the player before it was executed
+    Cancelled,
+    /// Handed to the game simulation
+    Executed,
+}
+
+impl GameActionState {
+    /// The value saved in the `action_state` column
+    pub fn to_database_string(self) -> String {
+        match self {
+            GameActionState::Queued => 0,
+            GameActionState::Cancelled => 1,
+            GameActionState::Executed => 2,
+        }
+        .to_string()
+    }
+}
+
+/// Every column of a game actions table after its `action_id` primary key
+pub(crate) const GAME_ACTIONS_COLUMNS: &[(&str, &str)] = &[
+    ("tick_scheduled", "INTEGER NOT NULL"),
+    ("player_action", "TEXT NOT NULL"),
+    ("action_state", "INTEGER NOT NULL"),
+];
+
+/// Creates a new Game Actions Table
+#[derive(Component, Debug, Clone)]
+pub struct CreateGameActionsTable {
+    pub game_id: GameId,
+}
+
+impl DatabaseSql for CreateGameActionsTable {
+    fn to_sql(&self) -> Option<(String, Vec<String>)> {
+        let game_id = self.game_id.id_as_string();
+        Some((
+            format!(
+                "CREATE TABLE \"game_actions_{}\" (action_id TEXT PRIMARY KEY NOT NULL, {})",
+                game_id,
+                column_definitions(GAME_ACTIONS_COLUMNS)
+            ),
+            vec![],
+        ))
+    }
+}
+
+/// Inserts a newly queued action into a games actions table
+#[derive(Component, Debug, Clone)]
+pub struct InsertGameActionsRow {
+    pub game_id: GameId,
+    pub player_action: PlayerAction,
+}
+
+impl DatabaseSql for InsertGameActionsRow {
+    fn to_sql(&self) -> Option<(String, Vec<String>)> {
+        Some((
+            format!(
+                "insert into \"game_actions_{}\" (action_id, tick_scheduled, player_action, action_state) values (?1, ?2, ?3, ?4)",
+                self.game_id.id_as_string()
+            ),
+            vec![
+                self.player_action.action_id.to_database_string()?,
+                self.player_action.tick_scheduled.to_string(),
+                self.player_action.to_database_string()?,
+                GameActionState::Queued.to_database_string(),
+            ],
+        ))
+    }
+}
+
+/// Marks a queued action as cancelled. Actions that were already executed are left alone
+#[derive(Component, Debug, Clone)]
+pub struct CancelGameAction {
+    pub game_id: GameId,
+    pub action_id: ActionId,
+}
+
+impl DatabaseSql for CancelGameAction {
+    fn to_sql(&self) -> Option<(String, Vec<String>)> {
+        Some((
+            format!(
+                "update \"game_actions_{}\" set action_state = ?1 where action_id = ?2 and action_state = ?3",
+                self.game_id.id_as_string()
+            ),
+            vec![
+                GameActionState::Cancelled.to_database_string(),
+                self.action_id.to_database_string()?,
+                GameActionState::Queued.to_database_string(),
+            ],
+        ))
+    }
+}
+
+/// Replaces a queued action with its edited version. Actions that were already executed are left alone
+#[derive(Component, Debug, Clone)]
+pub struct EditGameAction {
+    pub game_id: GameId,
+    pub player_action: PlayerAction,
+}
+
+impl DatabaseSql for EditGameAction {
+    fn to_sql(&self) -> Option<(String, Vec<String>)> {
+        Some((
+            format!(
+                "update \"game_actions_{}\" set tick_scheduled = ?1, player_action = ?2 where action_id = ?3 and action_state = ?4",
+                self.game_id.id_as_string()
+            ),
+            vec![
+                self.player_action.tick_scheduled.to_string(),
+                self.player_action.to_database_string()?,
+                self.player_action.action_id.to_database_string()?,
+                GameActionState::Queued.to_database_string(),
+            ],
+        ))
+    }
+}
+
+/// Marks every queued action scheduled on or before `up_to_tick` as executed
+#[derive(Component, Debug, Clone)]
+pub struct ExecuteGameActions {
+    pub game_id: GameId,
+    pub up_to_tick: u64,
+}
+
+impl DatabaseSql for ExecuteGameActions {
+    fn to_sql(&self) -> Option<(String, Vec<String>)> {
+        Some((
+            format!(
+                "update \"game_actions_{}\" set action_state = ?1 where tick_scheduled <= ?2 and action_state = ?3",
+                self.game_id.id_as_string()
+            ),
+            vec![
+                GameActionState::Executed.to_database_string(),
+                self.up_to_tick.to_string(),
+                GameActionState::Queued.to_database_string(),
+            ],
+        ))
+    }
+}
+
+/// Reads every action in a games actions table that is still queued, in the order they are scheduled. Games saved before
+/// actions were stored have no actions table and so have no queued actions
+pub fn queued_game_actions(
+    connection: &Connection,
+    game_id: &GameId,
+) -> Result<Vec<PlayerAction>, rusqlite::Error> {
+    let table_exists = connection.query_row(
+        "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1)",
+        [format!("game_actions_{}", game_id.id_as_string())],
+        |row| row.get::<_, bool>(0),
+    )?;
+    if !table_exists {
+        return Ok(vec![]);
+    }
+
+    let mut stmt = connection.prepare(&format!(
+        "SELECT player_action FROM \"game_actions_{}\" where action_state = ?1 ORDER BY tick_scheduled",
+        game_id.id_as_string()
+    ))?;
+    let rows = stmt.query_map([GameActionState::Queued.to_database_string()], |row| {
+        row.get::<_, String>(0)
+    })?;
+
+    let mut actions = vec![];
+    for row in rows {
+        let row = row?;
+        match serde_json::from_str::<PlayerAction>(&row) {
+            Ok(action) => actions.push(action),
+            Err(err) => error!(
+                "Skipping saved action {} that could not be read: {}",
+                row, err
+            ),
+        }
+    }
+    Ok(actions)
+}
@@ -3,11 +3,38 @@
 use bevy::ecs::component::Component;
 use bevy_state_curves::prelude::SteppedCurve;
 use general::{
+    auth_server::AccountId,
     game_meta::GameId,
-    objects::core_components::{ObjectGeneral, ObjectId, ObjectPosition},
+    game_simulation::economy::PlayerResources,
+    objects::core_components::{ObjectGeneral, ObjectId},
 };
 
-use crate::database_traits::{DatabaseData, DatabaseSql, PureDatabaseData};
+use crate::{
+    database_traits::{DatabaseData, DatabaseSql, PureDatabaseData},
+    schemes::column_definitions,
+};
+
+/// Every column of a game players table after its `account_id` primary key
+pub(crate) const GAME_PLAYERS_COLUMNS: &[(&str, &str)] = &[
+    ("last_sign_in", "TEXT"),
+    ("last_state_sent", "TEXT"),
+    ("last_sign_out", "TEXT"),
+    ("faction", "TEXT"),
+    ("color", "TEXT"),
+    ("sc_player_resources", "TEXT"),
+];
+
+/// Every column of a game curves table after its `object_id` primary key
+pub(crate) const GAME_CURVES_COLUMNS: &[(&str, &str)] = &[
+    ("sc_object_general", "TEXT NOT NULL"),
+    ("sc_object_position", "TEXT"),
+    ("lc_object_position", "TEXT"),
+    ("sc_outpost_connections", "TEXT"),
+    ("sc_outpost_garrison", "TEXT"),
+    ("sc_army_units", "TEXT"),
+    ("sc_army_route", "TEXT"),
+    ("sc_outpost_type", "TEXT"),
+];
 
 /// Creates a new Game Players Table
 #[derive(Component, Debug, Clone)]
@@ -20,9 +47,12 @@ impl DatabaseSql for CreateGamePlayersTable {
         let game_id = self.game_id.id_as_string();
 
         Some((
-            format!("CREATE TABLE \"game_players_{}\" (account_id TEXT PRIMARY KEY NOT NULL, last_sign_in TEXT, last_state_sent TEXT, last_sign_out TEXT, faction TEXT, color TEXT)", game_id),
-            vec![
-            ],
+            format!(
+                "CREATE TABLE \"game_players_{}\" (account_id TEXT PRIMARY KEY NOT NULL, {})",
+                game_id,
+                column_definitions(GAME_PLAYERS_COLUMNS)
+            ),
+            vec![],
         ))
     }
 }
@@ -36,10 +66,13 @@ pub struct CreateGameCurvesTable {
 impl DatabaseSql for CreateGameCurvesTable {
     fn to_sql(&self) -> Option<(String, Vec<String>)> {
         let game_id = self.game_id.id_as_string();
-        Some(    (
-            format!("CREATE TABLE \"game_curves_{}\" (object_id TEXT PRIMARY KEY NOT NULL, sc_object_general TEXT NOT NULL, sc_object_position TEXT NOT NULL)", game_id),
-            vec![
-            ],
+        Some((
+            format!(
+                "CREATE TABLE \"game_curves_{}\" (object_id TEXT PRIMARY KEY NOT NULL, {})",
+                game_id,
+                column_definitions(GAME_CURVES_COLUMNS)
+            ),
+            vec![],
         ))
     }
 }
@@ -49,58 +82,95 @@ impl DatabaseSql for CreateGameCurvesTable {
 pub struct InsertGameCurvesRow {
     game_id: GameId,
     object_id: PureDatabaseData,
-    object_general: Option<PureDatabaseData>,
-    object_position: PureDatabaseData,
+    database_data: Vec<PureDatabaseData>,
 }
 
 impl InsertGameCurvesRow {
+    /// Creates a new row with the data every object has. `object_position` is either a stepped or a linear position curve
     pub fn new_row(
         game_id: GameId,
         object_id: &ObjectId,
-        object_general: Option<ObjectGeneral>,
-        object_position: &SteppedCurve<ObjectPosition>,
+        object_general: &SteppedCurve<ObjectGeneral>,
+        object_position: &impl DatabaseData,
     ) -> Option<InsertGameCurvesRow> {
-        let Some(object_id) = object_id.to_database_data() else {
-            return None;
-        };
-
-        let Some(object_position) = object_position.to_database_data() else {
-            return None;
-        };
-
-        let object_general = match object_general {
-            Some(og) => og.to_database_data(),
-            None => None,
-        };
+        let object_id = object_id.to_database_data()?;
+        let object_position = object_position.to_database_data()?;
+        let object_general = object_general.to_database_data()?;
 
         Some(InsertGameCurvesRow {
             game_id,
             object_id,
-            object_general,
-            object_position,
+            database_data: vec![object_general, object_position],
         })
     }
+
+    /// Adds the given data to the row. Returns None if the data could not be converted into [`PureDatabaseData`]
+    pub fn with_data(mut self, data: &impl DatabaseData) -> Option<InsertGameCurvesRow> {
+        self.database_data.push(data.to_database_data()?);
+        Some(self)
+    }
 }
 
 impl DatabaseSql for InsertGameCurvesRow {
     fn to_sql(&self) -> Option<(String, Vec<String>)> {
         let game_id = self.game_id.id_as_string();
 
-        match &self.object_general {
-        Some(object_general) => {
-                Some((format!("insert into \"game_curves_{}\" (object_id, sc_object_general, sc_object_position) values (?1, ?2, ?3)", game_id),
-            vec![
-                self.object_id.data.clone(),
-                object_general.data.clone(),
-                self.object_position.data.clone(),
-                ],))
-            }
-            None => Some((format!("insert into \"game_curves_{}\" (object_id, sc_object_general, sc_object_position) values (?1, ?2, ?3)", game_id),
-            vec![
-                self.object_id.data.clone(),
-                ObjectGeneral::default().to_database_data().unwrap().data,
-                self.object_position.data.clone(),
-            ],)),
+        let mut columns = vec![self.object_id.column_name.clone()];
+        let mut values = vec!["?1".to_string()];
+        let mut params = vec![self.object_id.data.clone()];
+        for (index, data) in self.database_data.iter().enumerate() {
+            columns.push(data.column_name.clone());
+            values.push(format!("?{}", index + 2));
+            params.push(data.data.clone());
         }
+
+        Some((
+            format!(
+                "insert into \"game_curves_{}\" ({}) values ({})",
+                game_id,
+                columns.join(", "),
+                values.join(", ")
+            ),
+            params,
+        ))
+    }
+}
+
+/// Inserts a new row in a games players table
+#[derive(Component, Debug, Clone)]
+pub struct InsertGamePlayersRow {
+    game_id: GameId,
+    account_id: PureDatabaseData,
+    player_resources: PureDatabaseData,
+}
+
+impl InsertGamePlayersRow {
+    pub fn new_row(
+        game_id: GameId,
+        account_id: &AccountId,
+        player_resources: &SteppedCurve<PlayerResources>,
+    ) -> Option<InsertGamePlayersRow> {
+        Some(InsertGamePlayersRow {
+            game_id,
+            account_id: account_id.to_database_data()?,
+            player_resources: player_resources.to_database_data()?,
+        })
+    }
+}
+
+impl DatabaseSql for InsertGamePlayersRow {
+    fn to_sql(&self) -> Option<(String, Vec<String>)> {
+        Some((
+            format!(
+                "insert into \"game_players_{}\" ({}, {}) values (?1, ?2)",
+                self.game_id.id_as_string(),
+                self.account_id.column_name,
+                self.player_resources.column_name
+            ),
+            vec![
+                self.account_id.data.clone(),
+                self.player_resources.data.clone(),
+            ],
+        ))
     }
 }
@@ -1,26 +1,53 @@
 use bevy::ecs::component::Component;
 use general::{
     auth_server::AccountId,
-    game_meta::{GameId, GamePlayers},
-    objects::ObjectIdService,
+    game_meta::{GameId, GamePlayers, GameSeed, NewGameSettings},
+    objects::{core_components::ObjectId, ObjectIdService},
 };
 
 use crate::database_traits::DatabaseSql;
 
+/// Every column of the games meta table after its `game_id` primary key
+pub(crate) const GAMES_META_COLUMNS: &[(&str, &str)] = &[
+    ("game_players", "TEXT"),
+    ("max_players", "INTEGER"),
+    ("pending_players", "TEXT"),
+    ("game_state", "INTEGER"),
+    ("has_space", "INTEGER"),
+    ("object_id_service", "TEXT"),
+    ("owning_player", "TEXT"),
+    ("seed", "TEXT"),
+    ("game_settings", "TEXT"),
+    ("game_tick", "INTEGER"),
+    ("started_at", "INTEGER"),
+    ("wake_at", "TEXT"),
+    ("player_visibility", "TEXT"),
+    ("start_slots", "TEXT"),
+];
+
 #[derive(Component, Debug, Clone)]
 pub struct InsertGamesMetaRow {
     pub game_id: GameId,
     pub max_players: u8,
     pub owning_player: Option<AccountId>,
     pub object_id_service: ObjectIdService,
+    pub seed: GameSeed,
+    pub settings: NewGameSettings,
+    /// The unix time in milliseconds that tick 0 of the game happened at. Replaced once the game is started
+    pub started_at: u64,
+    /// The outposts players can be given as their home when the game starts. Empty if any outpost can be a home
+    pub start_slots: Vec<ObjectId>,
 }
 
 impl DatabaseSql for InsertGamesMetaRow {
     fn to_sql(&self) -> Option<(String, Vec<String>)> {
         let game_id = self.game_id.to_json();
+        // New games start in memory so they are loaded straight away if the server restarts
+        let wake_at = serde_json::to_string(&Some(0u64)).unwrap();
+        let start_slots = serde_json::to_string(&self.start_slots).ok()?;
         match &self.owning_player {
             Some(player) => Some((
-                "insert into games_meta (game_id, game_players, max_players, game_state, has_space, object_id_service, owning_player) values (?1, ?2, ?3, ?4, ?5, ?6, ?7)".to_string(),
+                "insert into games_meta (game_id, game_players, max_players, game_state, has_space, object_id_service, owning_player, seed, game_settings, game_tick, started_at, wake_at, start_slots) values (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)".to_string(),
                 vec![
                     game_id,
                     serde_json::to_string(&GamePlayers::default())
@@ -32,10 +59,17 @@ impl DatabaseSql for InsertGamesMetaRow {
                     .unwrap(),
                     serde_json::to_string(player)
                     .unwrap(),
+                    self.seed.seed.to_string(),
+                    serde_json::to_string(&self.settings)
+                    .unwrap(),
+                    0.to_string(),
+                    self.started_at.to_string(),
+                    wake_at,
+                    start_slots,
                 ],
             )),
             None => Some((
-                "insert into games_meta (game_id, game_players, max_players, game_state, has_space,  object_id_service) values (?1, ?2, ?3, ?4, ?5, ?6)".to_string(),
+                "insert into games_meta (game_id, game_players, max_players, game_state, has_space,  object_id_service, seed, game_settings, game_tick, started_at, wake_at, start_slots) values (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)".to_string(),
                 vec![
                     game_id,
                     serde_json::to_string(&GamePlayers::default())
@@ -44,7 +78,14 @@ impl DatabaseSql for InsertGamesMetaRow {
                     0.to_string(),
                     1.to_string(),
                     serde_json::to_string(&self.object_id_service)
-                    .unwrap()
+                    .unwrap(),
+                    self.seed.seed.to_string(),
+                    serde_json::to_string(&self.settings)
+                    .unwrap(),
+                    0.to_string(),
+                    self.started_at.to_string(),
+                    wake_at,
+                    start_slots,
                 ],
             )),
         }
@@ -0,0 +1,194 @@
+//! Responsible for bringing game server databases saved by older versions of the server up to the current schema.
+//!
+//! Tables are created if they are missing, including the actions table of games saved before actions were stored, and
+//! every column the current schema expects is added to tables created before it existed, so migrating is safe to run every
+//! time the server starts. Rows saved before a column existed hold NULL in it,
+//! and [`crate::loading`] defaults those values when the game is read
+
+use rusqlite::{Connection, Transaction};
+
+use crate::schemes::column_definitions;
+
+use super::{
+    game_actions::GAME_ACTIONS_COLUMNS,
+    game_tables::{GAME_CURVES_COLUMNS, GAME_PLAYERS_COLUMNS},
+    games_meta::GAMES_META_COLUMNS,
+};
+
+/// Migrates the games meta table and every games curves, players and actions table to the current schema
+pub fn migrate_database(connection: &mut Connection) -> Result<(), rusqlite::Error> {
+    let tx = connection.transaction()?;
+    tx.execute(
+        &format!(
+            "CREATE TABLE IF NOT EXISTS games_meta (game_id TEXT PRIMARY KEY NOT NULL, {})",
+            column_definitions(GAMES_META_COLUMNS)
+        ),
+        (),
+    )?;
+    add_missing_columns(&tx, "games_meta", GAMES_META_COLUMNS)?;
+
+    for table in table_names(&tx)? {
+        if let Some(game_id) = table.strip_prefix("game_curves_") {
+            make_stepped_position_nullable(&tx, &table)?;
+            add_missing_columns(&tx, &table, GAME_CURVES_COLUMNS)?;
+            tx.execute(
+                &format!(
+                    "CREATE TABLE IF NOT EXISTS \"game_actions_{}\" (action_id TEXT PRIMARY KEY NOT NULL, {})",
+                    game_id,
+                    column_definitions(GAME_ACTIONS_COLUMNS)
+                ),
+                (),
+            )?;
+        } else if table.starts_with("game_players_") {
+            add_missing_columns(&tx, &table, GAME_PLAYERS_COLUMNS)?;
+        }
+    }
+    tx.commit()
+}
+
+struct TableColumn {
+    name: String,
+    not_null: bool,
+}
+
+fn table_names(tx: &Transaction) -> Result<Vec<String>, rusqlite::Error> {
+    let mut stmt = tx.prepare("SELECT name FROM sqlite_master WHERE type = 'table'")?;
+    let rows = stmt.query_map((), |row| row.get(0))?;
+    rows.collect()
+}
+
+fn table_columns(tx: &Transaction, table: &str) -> Result<Vec<TableColumn>, rusqlite::Error> {
+    let mut stmt = tx.prepare(&format!("PRAGMA table_info(\"{}\")", table))?;
+    let rows = stmt.query_map((), |row| {
+        Ok(TableColumn {
+            name: row.get(1)?,
+            not_null: row.get::<_, i64>(3)? != 0,
+        })
+    })?;
+    rows.collect()
+}
+
+fn add_missing_columns(
+    tx: &Transaction,
+    table: &str,
+    columns: &[(&str, &str)],
+) -> Result<(), rusqlite::Error> {
+    let existing_columns = table_columns(tx, table)?;
+    for (name, column_type) in columns {
+        if existing_columns.iter().any(|column| column.name == *name) {
+            continue;
+        }
+        tx.execute(
+            &format!(
+                "ALTER TABLE \"{}\" ADD COLUMN {} {}",
+                table, name, column_type
+            ),
+            (),
+        )?;
+    }
+    Ok(())
+}
+
+/// Curves tables created before armies existed require a stepped position, which armies do not have. Sqlite can not drop a
+/// NOT NULL constraint so the table is rebuilt with every column it already had
+fn make_stepped_position_nullable(tx: &Transaction, table: &str) -> Result<(), rusqlite::Error> {
+    let columns = table_columns(tx, table)?;
+    if !columns
+        .iter()
+        .any(|column| column.name == "sc_object_position" && column.not_null)
+    {
+        return Ok(());
+    }
+
+    let copied_columns = columns
+        .iter()
+        .map(|column| column.name.as_str())
+        .filter(|name| {
+            *name == "object_id" || GAME_CURVES_COLUMNS.iter().any(|(column, _)| column == name)
+        })
+        .collect::<Vec<&str>>()
+        .join(", ");
+    let old_table = format!("{}_old", table);
+    tx.execute(
+        &format!("ALTER TABLE \"{}\" RENAME TO \"{}\"", table, old_table),
+        (),
+    )?;
+    tx.execute(
+        &format!(
+            "CREATE TABLE \"{}\" (object_id TEXT PRIMARY KEY NOT NULL, {})",
+            table,
+            column_definitions(GAME_CURVES_COLUMNS)
+        ),
+        (),
+    )?;
+    tx.execute(
+        &format!(
+            "INSERT INTO \"{}\" ({}) SELECT {} FROM \"{}\"",
+            table, copied_columns, copied_columns, old_table
+        ),
+        (),
+    )?;
+    tx.execute(&format!("DROP TABLE \"{}\"", old_table), ())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use rusqlite::Connection;
+
+    use super::migrate_database;
+
+    #[test]
+    fn migrates_tables_saved_before_the_current_schema() {
+        let mut connection = Connection::open_in_memory().unwrap();
+        connection
+            .execute_batch(
+                "CREATE TABLE games_meta (game_id TEXT PRIMARY KEY NOT NULL, game_players TEXT, max_players INTEGER, pending_players TEXT, game_state INTEGER, has_space INTEGER, object_id_service TEXT, owning_player TEXT);
+                CREATE TABLE \"game_curves_old\" (object_id TEXT PRIMARY KEY NOT NULL, sc_object_general TEXT NOT NULL, sc_object_position TEXT NOT NULL);
+                CREATE TABLE \"game_players_old\" (account_id TEXT PRIMARY KEY NOT NULL, last_sign_in TEXT, last_state_sent TEXT, last_sign_out TEXT, faction TEXT, color TEXT);
+                INSERT INTO \"game_curves_old\" VALUES ('outpost', 'general', 'position');",
+            )
+            .unwrap();
+
+        migrate_database(&mut connection).unwrap();
+        // Migrating an up to date database changes nothing
+        migrate_database(&mut connection).unwrap();
+
+        connection
+            .execute(
+                "INSERT INTO games_meta (game_id, seed, game_settings, game_tick, started_at, wake_at) VALUES ('game', '1', '{}', 0, 0, '0')",
+                (),
+            )
+            .unwrap();
+        // Armies only have a linear position
+        connection
+            .execute(
+                "INSERT INTO \"game_curves_old\" (object_id, sc_object_general, lc_object_position) VALUES ('army', 'general', 'position')",
+                (),
+            )
+            .unwrap();
+        connection
+            .execute(
+                "INSERT INTO \"game_players_old\" (account_id, sc_player_resources) VALUES ('player', '{}')",
+                (),
+            )
+            .unwrap();
+
+        // Games saved before actions were stored get an empty actions table
+        let queued_actions: i64 = connection
+            .query_row("SELECT COUNT(*) FROM \"game_actions_old\"", (), |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(queued_actions, 0);
+
+        let outpost_position: String = connection
+            .query_row(
+                "SELECT sc_object_position FROM \"game_curves_old\" WHERE object_id = 'outpost'",
+                (),
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(outpost_position, "position");
+    }
+}
@@ -2,17 +2,31 @@ use bevy::{
     app::Plugin,
     ecs::{system::Resource, world::World},
 };
-use bevy_state_curves::prelude::SteppedCurve;
+use bevy_state_curves::prelude::{LinearCurve, SteppedCurve};
 use general::{
-    clone_async_sender,
+    actions::{ActionId, PlayerAction},
+    auth_server::AccountId,
     game_meta::{GameId, GamePlayers},
-    objects::core_components::{ObjectGeneral, ObjectId, ObjectPosition},
+    game_simulation::{economy::PlayerResources, visibility::PlayerVisibility, SimulationTicks},
+    insert_buffered_async_sender,
+    objects::{
+        army::{ArmyRoute, ArmyUnits},
+        core_components::{ObjectGeneral, ObjectId, ObjectPosition},
+        outpost::{OutpostConnections, OutpostGarrison, OutpostType},
+        ObjectIdService,
+    },
 };
 
 use crate::database_traits::{DatabaseData, DatabaseTable, GameDatabaseTable};
 
 use self::{
-    game_tables::{CreateGameCurvesTable, CreateGamePlayersTable, InsertGameCurvesRow},
+    game_actions::{
+        CancelGameAction, CreateGameActionsTable, EditGameAction, ExecuteGameActions,
+        InsertGameActionsRow,
+    },
+    game_tables::{
+        CreateGameCurvesTable, CreateGamePlayersTable, InsertGameCurvesRow, InsertGamePlayersRow,
+    },
     games_meta::InsertGamesMetaRow,
 };
 
@@ -21,6 +35,7 @@ pub use super::DatabaseSchemeAppExtension;
 pub mod game_actions;
 pub mod game_tables;
 pub mod games_meta;
+pub mod migrations;
 
 pub(crate) struct GameServerPlugin;
 
@@ -29,35 +44,39 @@ impl Plugin for GameServerPlugin {
         app.insert_resource(GamesMetaTable);
         app.insert_resource(GameCurvesTable);
         app.insert_resource(GamesPlayersTable);
+        app.insert_resource(GameActionsTable);
 
         app.server_register_sql_action::<InsertGamesMetaRow>();
         app.server_register_sql_action::<InsertGameCurvesRow>();
         app.server_register_sql_action::<CreateGameCurvesTable>();
         app.server_register_sql_action::<CreateGamePlayersTable>();
+        app.server_register_sql_action::<InsertGamePlayersRow>();
+        app.server_register_sql_action::<CreateGameActionsTable>();
+        app.server_register_sql_action::<InsertGameActionsRow>();
+        app.server_register_sql_action::<CancelGameAction>();
+        app.server_register_sql_action::<EditGameAction>();
+        app.server_register_sql_action::<ExecuteGameActions>();
     }
 }
 
 /// Adds all the game server schemes to the server world
 pub(crate) fn setup_game_server_schemes(server_world: &World, game_world: &mut World) {
-    game_world.insert_resource(
-        clone_async_sender::<InsertGamesMetaRow>(server_world)
-            .expect("AsyncChannelSender<InsertGamesMetaRow> not found"),
-    );
-    game_world.insert_resource(
-        clone_async_sender::<InsertGameCurvesRow>(server_world)
-            .expect("AsyncChannelSender<InsertGameCurvesRow> not found"),
-    );
-    game_world.insert_resource(
-        clone_async_sender::<CreateGameCurvesTable>(server_world)
-            .expect("AsyncChannelSender<CreateGameCurvesTable> not found"),
-    );
-    game_world.insert_resource(
-        clone_async_sender::<CreateGamePlayersTable>(server_world)
-            .expect("AsyncChannelSender<CreateGamePlayersTable> not found"),
-    );
+    insert_buffered_async_sender::<InsertGamesMetaRow>(server_world, game_world)
+        .expect("AsyncChannelSender<InsertGamesMetaRow> not found");
+    insert_buffered_async_sender::<InsertGameCurvesRow>(server_world, game_world)
+        .expect("AsyncChannelSender<InsertGameCurvesRow> not found");
+    insert_buffered_async_sender::<CreateGameCurvesTable>(server_world, game_world)
+        .expect("AsyncChannelSender<CreateGameCurvesTable> not found");
+    insert_buffered_async_sender::<CreateGamePlayersTable>(server_world, game_world)
+        .expect("AsyncChannelSender<CreateGamePlayersTable> not found");
+    insert_buffered_async_sender::<InsertGamePlayersRow>(server_world, game_world)
+        .expect("AsyncChannelSender<InsertGamePlayersRow> not found");
+    insert_buffered_async_sender::<ExecuteGameActions>(server_world, game_world)
+        .expect("AsyncChannelSender<ExecuteGameActions> not found");
     game_world.insert_resource(GamesMetaTable);
     game_world.insert_resource(GameCurvesTable);
     game_world.insert_resource(GamesPlayersTable);
+    game_world.insert_resource(GameActionsTable);
 }
 
 #[derive(Resource)]
@@ -87,6 +106,15 @@ impl GameDatabaseTable for GamesPlayersTable {
     }
 }
 
+#[derive(Resource)]
+pub struct GameActionsTable;
+
+impl GameDatabaseTable for GameActionsTable {
+    fn table_name(&self, game_id: &GameId) -> String {
+        format!("game_actions_{}", game_id.id_as_string())
+    }
+}
+
 impl DatabaseData for GameId {
     fn to_database_string(&self) -> Option<String> {
         serde_json::to_string(self).ok()
@@ -107,6 +135,47 @@ impl DatabaseData for GamePlayers {
     }
 }
 
+impl DatabaseData for ObjectIdService {
+    fn to_database_string(&self) -> Option<String> {
+        serde_json::to_string(self).ok()
+    }
+
+    fn column_name(&self) -> &str {
+        "object_id_service"
+    }
+}
+
+impl DatabaseData for PlayerVisibility {
+    fn to_database_string(&self) -> Option<String> {
+        serde_json::to_string(self).ok()
+    }
+
+    fn column_name(&self) -> &str {
+        "player_visibility"
+    }
+}
+
+/// Saved as the tick the game has been simulated up to
+impl DatabaseData for SimulationTicks {
+    fn to_database_string(&self) -> Option<String> {
+        Some(self.current_tick.to_string())
+    }
+
+    fn column_name(&self) -> &str {
+        "game_tick"
+    }
+}
+
+impl DatabaseData for AccountId {
+    fn to_database_string(&self) -> Option<String> {
+        serde_json::to_string(self).ok()
+    }
+
+    fn column_name(&self) -> &str {
+        "account_id"
+    }
+}
+
 impl DatabaseData for ObjectId {
     fn to_database_string(&self) -> Option<String> {
         serde_json::to_string(self).ok()
@@ -117,13 +186,13 @@ impl DatabaseData for ObjectId {
     }
 }
 
-impl DatabaseData for ObjectGeneral {
+impl DatabaseData for SteppedCurve<ObjectGeneral> {
     fn to_database_string(&self) -> Option<String> {
         serde_json::to_string(self).ok()
     }
 
     fn column_name(&self) -> &str {
-        "object_general"
+        "sc_object_general"
     }
 }
 
@@ -136,3 +205,93 @@ impl DatabaseData for SteppedCurve<ObjectPosition> {
         "sc_object_position"
     }
 }
+
+impl DatabaseData for LinearCurve<ObjectPosition> {
+    fn to_database_string(&self) -> Option<String> {
+        serde_json::to_string(self).ok()
+    }
+
+    fn column_name(&self) -> &str {
+        "lc_object_position"
+    }
+}
+
+impl DatabaseData for SteppedCurve<OutpostConnections> {
+    fn to_database_string(&self) -> Option<String> {
+        serde_json::to_string(self).ok()
+    }
+
+    fn column_name(&self) -> &str {
+        "sc_outpost_connections"
+    }
+}
+
+impl DatabaseData for SteppedCurve<OutpostGarrison> {
+    fn to_database_string(&self) -> Option<String> {
+        serde_json::to_string(self).ok()
+    }
+
+    fn column_name(&self) -> &str {
+        "sc_outpost_garrison"
+    }
+}
+
+impl DatabaseData for SteppedCurve<ArmyUnits> {
+    fn to_database_string(&self) -> Option<String> {
+        serde_json::to_string(self).ok()
+    }
+
+    fn column_name(&self) -> &str {
+        "sc_army_units"
+    }
+}
+
+impl DatabaseData for SteppedCurve<ArmyRoute> {
+    fn to_database_string(&self) -> Option<String> {
+        serde_json::to_string(self).ok()
+    }
+
+    fn column_name(&self) -> &str {
+        "sc_army_route"
+    }
+}
+
+impl DatabaseData for SteppedCurve<OutpostType> {
+    fn to_database_string(&self) -> Option<String> {
+        serde_json::to_string(self).ok()
+    }
+
+    fn column_name(&self) -> &str {
+        "sc_outpost_type"
+    }
+}
+
+impl DatabaseData for SteppedCurve<PlayerResources> {
+    fn to_database_string(&self) -> Option<String> {
+        serde_json::to_string(self).ok()
+    }
+
+    fn column_name(&self) -> &str {
+        "sc_player_resources"
+    }
+}
+
+impl DatabaseData for ActionId {
+    fn to_database_string(&self) -> Option<String> {
+        serde_json::to_string(self).ok()
+    }
+
+    fn column_name(&self) -> &str {
+        "action_id"
+    }
+}
+
+impl DatabaseData for PlayerAction {
+    fn to_database_string(&self) -> Option<String> {
+        serde_json::to_string(self).ok()
+    }
+
+    fn column_name(&self) -> &str {
+        "player_action"
+    }
+}
@@ -17,6 +17,15 @@ use crate::{database_traits::DatabaseSql, ConnectionSchema, Database};
 pub mod auth_server;
 pub mod game_server;
 
+/// Joins `(name, type)` column pairs into the column definitions of a CREATE TABLE statement
+pub(crate) fn column_definitions(columns: &[(&str, &str)]) -> String {
+    columns
+        .iter()
+        .map(|(name, column_type)| format!("{} {}", name, column_type))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
 pub trait DatabaseSchemeAppExtension {
     fn server_register_sql_action<T: Send + Sync + 'static + Component + DatabaseSql + Debug>(
         &mut self,
@@ -35,13 +44,18 @@ impl DatabaseSchemeAppExtension for App {
     }
 }
 
+/// Marks an sql action that has been received but not executed yet. While any exist the database is still catching up on
+/// writes that were sent to it
+#[derive(Component)]
+pub struct PendingSqlAction;
+
 fn read_channel<T: Send + Sync + 'static + Component + DatabaseSql + Debug>(
     channel: Res<AsyncChannelReceiver<T>>,
     mut commands: Commands,
 ) {
     if let Ok(channel) = channel.reciever_channel.try_lock() {
         while let Ok(new_message) = channel.try_recv() {
-            commands.spawn(new_message);
+            commands.spawn((new_message, PendingSqlAction));
         }
     }
 }
@@ -53,23 +53,25 @@ impl Debug for UpdateRow {
 
 impl DatabaseSql for UpdateRow {
     fn to_sql(&self) -> Option<(String, Vec<String>)> {
-        let params_vec: Vec<String> = self
+        let mut params_vec: Vec<String> = self
             .database_data
             .iter()
             .filter_map(|x| x.to_database_string())
             .collect();
-        let mut sql_command = format!("UPDATE \"{}\" SET ", self.table_name);
-        for (index, data) in self.database_data.iter().enumerate() {
-            sql_command.push_str(&format!("{} = ?{}, ", data.column_name(), index + 1));
-        }
-        let Some(row_id_data) = self.row_id.to_database_string() else {
-            return None;
-        };
-        sql_command.push_str(&format!(
-            "WHERE {} = {}, ",
+        let columns: Vec<String> = self
+            .database_data
+            .iter()
+            .enumerate()
+            .map(|(index, data)| format!("{} = ?{}", data.column_name(), index + 1))
+            .collect();
+        params_vec.push(self.row_id.to_database_string()?);
+        let sql_command = format!(
+            "UPDATE \"{}\" SET {} WHERE {} = ?{}",
+            self.table_name,
+            columns.join(", "),
             self.row_id.column_name(),
-            row_id_data
-        ));
+            params_vec.len()
+        );
         Some((sql_command, params_vec))
     }
 }
@@ -32,6 +32,11 @@ use crate::{
     game_manager::client_game_connection::RemoveConnectedPlayerFromGameEvent,
 };
 
+use self::{player_actions::PlayerActionsPlugin, state_sync::StateSyncPlugin};
+
+pub mod player_actions;
+pub mod state_sync;
+
 pub struct GameServerPlugin;
 
 impl Plugin for GameServerPlugin {
@@ -42,6 +47,7 @@ impl Plugin for GameServerPlugin {
         >::default());
 
         app.listen_for_message::<ClientInitialConnect, WebSocketProvider>();
+        app.add_plugins((StateSyncPlugin, PlayerActionsPlugin));
 
         app.init_resource::<ConnectionIdPlayerIdMapping>()
             .init_resource::<PlayerIdGameIdMapping>();
@@ -0,0 +1,342 @@
+//! Responsible for players submitting, cancelling and editing actions in the game they are connected to.
+//!
+//! Every request is routed through the [`PlayerIdGameIdMapping`] to the [`GameInstance`] the player is connected to. Players
+//! can only cancel or edit their own actions, and only until the tick they are scheduled for is simulated. The player is
+//! answered with a [`PlayerActionAccepted`] or [`PlayerActionCancelled`], or with a [`PlayerActionRejected`] holding the
+//! [`ActionRejectionReason`] if the request failed.
+//!
+//! Submitted and edited actions are checked against the games curves on the tick they are scheduled for. The game can still
+//! change before that tick, so actions that fail once they are executed are reported with a [`PlayerActionFailed`]
+
+use bevy::{
+    app::{Plugin, Update},
+    ecs::{
+        event::EventReader,
+        schedule::IntoSystemConfigs,
+        system::{Query, Res},
+        world::Mut,
+    },
+    log::info,
+};
+use bevy_eventwork::{AppNetworkMessage, ConnectionId, Network, NetworkData};
+use bevy_eventwork_mod_websockets::WebSocketProvider;
+use core_library::{
+    actions::{ActionId, PlayerAction},
+    auth_server::AccountId,
+    game_meta::GameId,
+    game_simulation::{army_movement::validate_player_action, FailedActions},
+    network::ws_game_server::{
+        ActionRejectionReason, CancelPlayerAction, EditPlayerAction, PlayerActionAccepted,
+        PlayerActionCancelled, PlayerActionFailed, PlayerActionRejected, SubmitPlayerAction,
+    },
+    sqlite_database::schemes::game_server::game_actions::{
+        CancelGameAction, EditGameAction, InsertGameActionsRow,
+    },
+    AsyncChannelSender,
+};
+
+use crate::{
+    app::app_scheduling::ServerAuthenticatedSets,
+    game_manager::{GameIdMapping, GameInstance},
+    player_actions::{
+        cancel_player_action, edit_player_action, queue_player_action, queued_player_action,
+    },
+};
+
+use super::{ConnectionIdPlayerIdMapping, PlayerIdGameIdMapping};
+
+pub struct PlayerActionsPlugin;
+
+impl Plugin for PlayerActionsPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.listen_for_message::<SubmitPlayerAction, WebSocketProvider>();
+        app.listen_for_message::<CancelPlayerAction, WebSocketProvider>();
+        app.listen_for_message::<EditPlayerAction, WebSocketProvider>();
+        app.add_systems(
+            Update,
+            (
+                handle_submitted_actions,
+                handle_cancelled_actions,
+                handle_edited_actions,
+                report_failed_actions,
+            )
+                .in_set(ServerAuthenticatedSets::ClientCommunication),
+        );
+    }
+}
+
+/// Schedules every submitted action in the game the submitting player is connected to and answers the player
+fn handle_submitted_actions(
+    mut new_messages: EventReader<NetworkData<SubmitPlayerAction>>,
+    net: Res<Network<WebSocketProvider>>,
+    connection_mapping: Res<ConnectionIdPlayerIdMapping>,
+    player_game_id_mapping: Res<PlayerIdGameIdMapping>,
+    game_id_mapping: Res<GameIdMapping>,
+    insert_action_channel: Res<AsyncChannelSender<InsertGameActionsRow>>,
+    mut games: Query<&mut GameInstance>,
+) {
+    for message in new_messages.read() {
+        let result = player_game(
+            message.source(),
+            message.game_id,
+            &connection_mapping,
+            &player_game_id_mapping,
+            &game_id_mapping,
+            &mut games,
+        )
+        .and_then(|(player, mut game)| {
+            let player_action = PlayerAction {
+                action_id: ActionId::generate(),
+                tick_scheduled: message.tick_scheduled,
+                issued_by_player: player,
+                action: message.action.clone(),
+            };
+            validate_action(&mut game, &player_action)?;
+            queue_player_action(&mut game, player_action.clone(), &insert_action_channel);
+            Ok(player_action)
+        });
+
+        match result {
+            Ok(player_action) => {
+                let _ = net.send_message(
+                    *message.source(),
+                    PlayerActionAccepted {
+                        request_id: message.request_id,
+                        game_id: message.game_id,
+                        player_action,
+                    },
+                );
+            }
+            Err(reason) => reject(
+                &net,
+                message.source(),
+                message.request_id,
+                message.game_id,
+                reason,
+            ),
+        }
+    }
+}
+
+/// Cancels every requested action that is still queued and belongs to the requesting player
+fn handle_cancelled_actions(
+    mut new_messages: EventReader<NetworkData<CancelPlayerAction>>,
+    net: Res<Network<WebSocketProvider>>,
+    connection_mapping: Res<ConnectionIdPlayerIdMapping>,
+    player_game_id_mapping: Res<PlayerIdGameIdMapping>,
+    game_id_mapping: Res<GameIdMapping>,
+    cancel_action_channel: Res<AsyncChannelSender<CancelGameAction>>,
+    mut games: Query<&mut GameInstance>,
+) {
+    for message in new_messages.read() {
+        let result = player_game(
+            message.source(),
+            message.game_id,
+            &connection_mapping,
+            &player_game_id_mapping,
+            &game_id_mapping,
+            &mut games,
+        )
+        .and_then(|(player, mut game)| {
+            validate_ownership(&game, &player, &message.action_id)?;
+            cancel_player_action(&mut game, &message.action_id, &cancel_action_channel)
+                .ok_or(ActionRejectionReason::ActionNotQueued)
+        });
+
+        match result {
+            Ok(player_action) => {
+                let _ = net.send_message(
+                    *message.source(),
+                    PlayerActionCancelled {
+                        request_id: message.request_id,
+                        game_id: message.game_id,
+                        action_id: player_action.action_id,
+                    },
+                );
+            }
+            Err(reason) => reject(
+                &net,
+                message.source(),
+                message.request_id,
+                message.game_id,
+                reason,
+            ),
+        }
+    }
+}
+
+/// Replaces every requested action that is still queued and belongs to the requesting player
+fn handle_edited_actions(
+    mut new_messages: EventReader<NetworkData<EditPlayerAction>>,
+    net: Res<Network<WebSocketProvider>>,
+    connection_mapping: Res<ConnectionIdPlayerIdMapping>,
+    player_game_id_mapping: Res<PlayerIdGameIdMapping>,
+    game_id_mapping: Res<GameIdMapping>,
+    edit_action_channel: Res<AsyncChannelSender<EditGameAction>>,
+    mut games: Query<&mut GameInstance>,
+) {
+    for message in new_messages.read() {
+        let result = player_game(
+            message.source(),
+            message.game_id,
+            &connection_mapping,
+            &player_game_id_mapping,
+            &game_id_mapping,
+            &mut games,
+        )
+        .and_then(|(player, mut game)| {
+            validate_ownership(&game, &player, &message.action_id)?;
+            let player_action = PlayerAction {
+                action_id: message.action_id,
+                tick_scheduled: message.tick_scheduled,
+                issued_by_player: player,
+                action: message.action.clone(),
+            };
+            validate_action(&mut game, &player_action)?;
+            if !edit_player_action(&mut game, player_action.clone(), &edit_action_channel) {
+                return Err(ActionRejectionReason::ActionNotQueued);
+            }
+            Ok(player_action)
+        });
+
+        match result {
+            Ok(player_action) => {
+                let _ = net.send_message(
+                    *message.source(),
+                    PlayerActionAccepted {
+                        request_id: message.request_id,
+                        game_id: message.game_id,
+                        player_action,
+                    },
+                );
+            }
+            Err(reason) => reject(
+                &net,
+                message.source(),
+                message.request_id,
+                message.game_id,
+                reason,
+            ),
+        }
+    }
+}
+
+/// Tells the players that issued them about every action that could not be executed
+fn report_failed_actions(
+    mut games: Query<&mut GameInstance>,
+    net: Res<Network<WebSocketProvider>>,
+    connection_mapping: Res<ConnectionIdPlayerIdMapping>,
+) {
+    for mut game in games.iter_mut() {
+        let game_id = game.game_id;
+        let Some(mut failed_actions) = game.game_world.get_resource_mut::<FailedActions>() else {
+            continue;
+        };
+        if failed_actions.actions.is_empty() {
+            continue;
+        }
+        for (player_action, reason) in failed_actions.actions.drain(..) {
+            info!(
+                "Action {} in game {} failed: {:?}",
+                player_action.action_id.id,
+                game_id.id_as_string(),
+                reason
+            );
+            let Some((connection_id, _)) = connection_mapping.map.iter().find(|(_, account_id)| {
+                account_id.as_ref() == Some(&player_action.issued_by_player)
+            }) else {
+                continue;
+            };
+            let _ = net.send_message(
+                *connection_id,
+                PlayerActionFailed {
+                    game_id,
+                    action_id: player_action.action_id,
+                    reason,
+                },
+            );
+        }
+    }
+}
+
+/// Sends the connection a [`PlayerActionRejected`]
+fn reject(
+    net: &Network<WebSocketProvider>,
+    connection_id: &ConnectionId,
+    request_id: u64,
+    game_id: GameId,
+    reason: ActionRejectionReason,
+) {
+    let _ = net.send_message(
+        *connection_id,
+        PlayerActionRejected {
+            request_id,
+            game_id,
+            reason,
+        },
+    );
+}
+
+/// Returns the player behind the connection and the game they are connected to, if they are playing in `game_id`
+fn player_game<'a>(
+    connection_id: &ConnectionId,
+    game_id: GameId,
+    connection_mapping: &ConnectionIdPlayerIdMapping,
+    player_game_id_mapping: &PlayerIdGameIdMapping,
+    game_id_mapping: &GameIdMapping,
+    games: &'a mut Query<&mut GameInstance>,
+) -> Result<(AccountId, Mut<'a, GameInstance>), ActionRejectionReason> {
+    let Some(Some(player)) = connection_mapping.map.get(connection_id) else {
+        return Err(ActionRejectionReason::NotAuthenticated);
+    };
+    if player_game_id_mapping.map.get(player) != Some(&Some(game_id)) {
+        return Err(ActionRejectionReason::NotConnectedToGame);
+    }
+    let Some(mut game) = game_id_mapping
+        .map
+        .get(&game_id)
+        .and_then(|entity| games.get_mut(*entity).ok())
+    else {
+        return Err(ActionRejectionReason::GameNotFound);
+    };
+
+    let game_world = &mut game.game_world;
+    let is_playing = game_world
+        .query::<&AccountId>()
+        .iter(game_world)
+        .any(|account_id| account_id == player);
+    if !is_playing {
+        return Err(ActionRejectionReason::NotAPlayerInGame);
+    }
+    Ok((player.clone(), game))
+}
+
+/// Checks that an action can be scheduled on its tick and could be executed on it
+fn validate_action(
+    game: &mut GameInstance,
+    player_action: &PlayerAction,
+) -> Result<(), ActionRejectionReason> {
+    if player_action.tick_scheduled <= game.game_tick.game_tick {
+        return Err(ActionRejectionReason::TickAlreadySimulated);
+    }
+    validate_player_action(&mut game.game_world, player_action)?;
+    Ok(())
+}
+
+/// Checks that the action is still queued, its tick has not been reached and it was issued by `player`
+fn validate_ownership(
+    game: &GameInstance,
+    player: &AccountId,
+    action_id: &ActionId,
+) -> Result<(), ActionRejectionReason> {
+    // Actions whose tick has been reached are executed even if the game has not been simulated up to them yet
+    let Some(queued_action) = queued_player_action(game, action_id)
+        .filter(|action| action.tick_scheduled > game.game_tick.game_tick)
+    else {
+        return Err(ActionRejectionReason::ActionNotQueued);
+    };
+    if queued_action.issued_by_player != *player {
+        return Err(ActionRejectionReason::NotActionOwner);
+    }
+    Ok(())
+}
@@ -0,0 +1,363 @@
+//! Responsible for keeping connected players up to date with the game they are connected to.
+//!
+//! Whenever a game has been simulated further or its action forecasts changed, every player in its
+//! [`CurrentlyConnectedPlayers`] is sent a [`GameStateUpdate`] with the curves that changed since their last update, the
+//! objects that came into view and the forecasts of their queued actions. Curves are filtered down to the parts the player
+//! can see according to the games [`PlayerVisibility`] and to the ticks from their last update onward. Once a player has
+//! been sent an update, the visibility segments before it are forgotten.
+//!
+//! Players that just connected to a game are sent a [`GameStateSnapshot`] instead, which every later update builds on
+
+use bevy::{
+    app::{Plugin, Update},
+    ecs::{
+        change_detection::{DetectChanges, DetectChangesMut},
+        component::Tick,
+        schedule::IntoSystemConfigs,
+        system::{Query, Res, Resource},
+        world::{Ref, World},
+    },
+    utils::HashMap,
+};
+use bevy_eventwork::Network;
+use bevy_eventwork_mod_websockets::WebSocketProvider;
+use bevy_state_curves::prelude::{CurveTrait, LinearCurve, SteppedCurve};
+use core_library::{
+    auth_server::AccountId,
+    game_meta::{GameId, NewGameSettings},
+    game_simulation::{
+        economy::PlayerResources,
+        forecasts::ActionForecasts,
+        visibility::{PlayerVisibility, VisibilitySet, VisibleSegment},
+        SimulationTicks,
+    },
+    network::ws_game_server::{GameStateSnapshot, GameStateUpdate, ObjectCurves},
+    objects::{
+        army::{ArmyRoute, ArmyUnits},
+        core_components::{ObjectGeneral, ObjectId, ObjectPosition},
+        outpost::{OutpostConnections, OutpostGarrison, OutpostType},
+    },
+};
+
+use crate::{app::app_scheduling::ServerAuthenticatedSets, game_manager::GameInstance};
+
+use super::{ConnectionIdPlayerIdMapping, CurrentlyConnectedPlayers};
+
+pub struct StateSyncPlugin;
+
+impl Plugin for StateSyncPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.add_systems(
+            Update,
+            sync_game_state.in_set(ServerAuthenticatedSets::ClientCommunication),
+        );
+    }
+}
+
+/// The last update that was sent to a player
+#[derive(Clone, Copy)]
+pub struct LastStateSent {
+    /// The tick the game had been simulated up to
+    pub game_tick: u64,
+    /// The change tick of the game world, every curve changed after it has not been sent yet
+    pub change_tick: Tick,
+}
+
+/// Inserted into game worlds to track the last update sent to each player
+#[derive(Resource, Default)]
+pub struct PlayerStateSync {
+    pub players: HashMap<AccountId, LastStateSent>,
+}
+
+/// Sends a [`GameStateUpdate`] to every connected player whose game has been simulated since their last update
+fn sync_game_state(
+    mut games: Query<(&mut GameInstance, &CurrentlyConnectedPlayers)>,
+    net: Res<Network<WebSocketProvider>>,
+    connection_mapping: Res<ConnectionIdPlayerIdMapping>,
+) {
+    for (mut game, connected_players) in games.iter_mut() {
+        let game_id = game.game_id;
+        let game_world = &mut game.game_world;
+        game_world.init_resource::<PlayerStateSync>();
+        let game_tick = game_world
+            .get_resource::<SimulationTicks>()
+            .map(|ticks| ticks.current_tick)
+            .unwrap_or_default();
+
+        for player in connected_players.players.iter() {
+            let last_sent = game_world
+                .resource::<PlayerStateSync>()
+                .players
+                .get(player)
+                .copied();
+            // Forecasts change whenever a player changes their queued actions, even if the game was not simulated. Reading
+            // them through `Mut` does not mark them as changed
+            let this_run = game_world.change_tick();
+            let forecasts_changed = game_world
+                .get_resource_mut::<ActionForecasts>()
+                .map(|forecasts| forecasts.last_changed());
+            if last_sent.is_some_and(|last_sent| {
+                last_sent.game_tick == game_tick
+                    && !forecasts_changed.is_some_and(|last_changed| {
+                        last_changed.is_newer_than(last_sent.change_tick, this_run)
+                    })
+            }) {
+                continue;
+            }
+
+            let Some((connection_id, _)) = connection_mapping
+                .map
+                .iter()
+                .find(|(_, account_id)| account_id.as_ref() == Some(player))
+            else {
+                continue;
+            };
+
+            // Everything changed up to and including this change tick is included in the update
+            let change_tick = game_world.increment_change_tick();
+            let update = build_state_update(game_world, game_id, player, last_sent);
+            let _ = net.send_message(*connection_id, update);
+
+            game_world.resource_mut::<PlayerStateSync>().players.insert(
+                player.clone(),
+                LastStateSent {
+                    game_tick,
+                    change_tick,
+                },
+            );
+        }
+
+        forget_sent_visibility(game_world, connected_players, game_tick);
+    }
+}
+
+/// Forgets the visibility segments every player has already been sent. Players that are not connected are sent a snapshot
+/// when they connect, which only needs the last segment of every object
+fn forget_sent_visibility(
+    game_world: &mut World,
+    connected_players: &CurrentlyConnectedPlayers,
+    game_tick: u64,
+) {
+    let sent_ticks: HashMap<AccountId, u64> = game_world
+        .resource::<PlayerStateSync>()
+        .players
+        .iter()
+        .filter(|(player, _)| connected_players.players.contains(*player))
+        .map(|(player, last_sent)| (player.clone(), last_sent.game_tick))
+        .collect();
+    let Some(mut visibility) = game_world.get_resource_mut::<PlayerVisibility>() else {
+        return;
+    };
+    // Forgetting segments only saves memory, so the visibility does not have to be saved into the database again
+    for (player, player_visibility) in visibility.bypass_change_detection().players.iter_mut() {
+        player_visibility.forget_before(sent_ticks.get(player).copied().unwrap_or(game_tick));
+    }
+}
+
+/// Builds the full snapshot of the game for a player that just connected and records it as the last state they were sent,
+/// so that the updates that follow only contain what changed since. Returns None if the game world has no settings
+pub fn build_state_snapshot(
+    game_world: &mut World,
+    game_id: GameId,
+    player: &AccountId,
+) -> Option<GameStateSnapshot> {
+    let settings = game_world.get_resource::<NewGameSettings>()?.clone();
+    let players = game_world
+        .query::<&AccountId>()
+        .iter(game_world)
+        .cloned()
+        .collect();
+
+    let change_tick = game_world.increment_change_tick();
+    let GameStateUpdate {
+        tick,
+        objects,
+        player_resources,
+        action_forecasts,
+        ..
+    } = build_state_update(game_world, game_id, player, None);
+
+    game_world
+        .get_resource_or_insert_with(PlayerStateSync::default)
+        .players
+        .insert(
+            player.clone(),
+            LastStateSent {
+                game_tick: tick,
+                change_tick,
+            },
+        );
+
+    Some(GameStateSnapshot {
+        game_id,
+        tick,
+        settings,
+        players,
+        objects,
+        player_resources,
+        action_forecasts,
+    })
+}
+
+/// Builds the update for `player` containing everything that changed or came into view since `last_sent`. Changed curves
+/// only hold the state on the tick of `last_sent` and the keyframes after it. Everything the player can see is included
+/// when they have not been sent an update yet
+pub fn build_state_update(
+    game_world: &mut World,
+    game_id: GameId,
+    player: &AccountId,
+    last_sent: Option<LastStateSent>,
+) -> GameStateUpdate {
+    let game_tick = game_world
+        .get_resource::<SimulationTicks>()
+        .map(|ticks| ticks.current_tick)
+        .unwrap_or_default();
+    let this_run = game_world.change_tick();
+    let from_tick = last_sent
+        .map(|last_sent| last_sent.game_tick)
+        .unwrap_or_default();
+    let changed = |last_changed: Tick| match last_sent {
+        Some(last_sent) => last_changed.is_newer_than(last_sent.change_tick, this_run),
+        None => true,
+    };
+
+    let mut objects = vec![];
+    let mut query = game_world.query::<(
+        &ObjectId,
+        Ref<SteppedCurve<ObjectGeneral>>,
+        Option<Ref<SteppedCurve<ObjectPosition>>>,
+        Option<Ref<LinearCurve<ObjectPosition>>>,
+        Option<Ref<SteppedCurve<OutpostConnections>>>,
+        Option<Ref<SteppedCurve<OutpostGarrison>>>,
+        Option<Ref<SteppedCurve<OutpostType>>>,
+        Option<Ref<SteppedCurve<ArmyUnits>>>,
+        Option<Ref<SteppedCurve<ArmyRoute>>>,
+    )>();
+    let empty_visibility = VisibilitySet::default();
+    let visibility = game_world
+        .get_resource::<PlayerVisibility>()
+        .and_then(|visibility| visibility.players.get(player))
+        .unwrap_or(&empty_visibility);
+    for (
+        object_id,
+        general,
+        stepped_position,
+        linear_position,
+        connections,
+        garrison,
+        outpost_type,
+        army_units,
+        army_route,
+    ) in query.iter(game_world)
+    {
+        let segments = visibility.segments(object_id);
+        if segments.is_empty() {
+            continue;
+        }
+        let came_into_view = match last_sent {
+            Some(last_sent) => {
+                !visibility.can_see(object_id, last_sent.game_tick)
+                    && visibility.can_see(object_id, game_tick)
+            }
+            None => true,
+        };
+        let any_changed = came_into_view
+            || [
+                Some(general.last_changed()),
+                stepped_position.as_ref().map(|curve| curve.last_changed()),
+                linear_position.as_ref().map(|curve| curve.last_changed()),
+                connections.as_ref().map(|curve| curve.last_changed()),
+                garrison.as_ref().map(|curve| curve.last_changed()),
+                outpost_type.as_ref().map(|curve| curve.last_changed()),
+                army_units.as_ref().map(|curve| curve.last_changed()),
+                army_route.as_ref().map(|curve| curve.last_changed()),
+            ]
+            .into_iter()
+            .flatten()
+            .any(changed);
+        if !any_changed {
+            continue;
+        }
+
+        objects.push(ObjectCurves {
+            object_id: *object_id,
+            object_general: Some(visible_curve(&*general, segments, from_tick, game_tick)),
+            stepped_position: stepped_position
+                .map(|curve| visible_curve(&*curve, segments, from_tick, game_tick)),
+            linear_position: linear_position
+                .map(|curve| visible_curve(&*curve, segments, from_tick, game_tick)),
+            outpost_connections: connections
+                .map(|curve| visible_curve(&*curve, segments, from_tick, game_tick)),
+            outpost_garrison: garrison
+                .map(|curve| visible_curve(&*curve, segments, from_tick, game_tick)),
+            outpost_type: outpost_type
+                .map(|curve| visible_curve(&*curve, segments, from_tick, game_tick)),
+            army_units: army_units
+                .map(|curve| visible_curve(&*curve, segments, from_tick, game_tick)),
+            army_route: army_route
+                .map(|curve| visible_curve(&*curve, segments, from_tick, game_tick)),
+        });
+    }
+
+    let player_resources = game_world
+        .query::<(&AccountId, Ref<SteppedCurve<PlayerResources>>)>()
+        .iter(game_world)
+        .find(|(account_id, resources)| *account_id == player && changed(resources.last_changed()))
+        .map(|(_, resources)| {
+            let mut curve = SteppedCurve::<PlayerResources>::new();
+            copy_keyframes(&*resources, &mut curve, from_tick, u64::MAX);
+            curve
+        });
+
+    let action_forecasts = game_world
+        .get_resource_mut::<ActionForecasts>()
+        .filter(|forecasts| changed(forecasts.last_changed()))
+        .map(|forecasts| forecasts.player_forecasts(player));
+
+    GameStateUpdate {
+        game_id,
+        tick: game_tick,
+        objects,
+        player_resources,
+        action_forecasts,
+    }
+}
+
+/// Returns a copy of `curve` with only the keyframes from `from_tick` onward that fall within `segments`. The state at the
+/// start of every segment, or on `from_tick` for a segment that started before it, is included so that the curve is correct
+/// for the whole segment.
+///
+/// Objects that are visible on `game_tick` also include every keyframe after it, so that players can see where visible
+/// armies are going
+pub fn visible_curve<T: Clone, C: CurveTrait<T>>(
+    curve: &C,
+    segments: &[VisibleSegment],
+    from_tick: u64,
+    game_tick: u64,
+) -> C {
+    let mut visible = C::new();
+    for segment in segments.iter().filter(|segment| segment.end >= from_tick) {
+        let end = if segment.contains(game_tick) {
+            u64::MAX
+        } else {
+            segment.end
+        };
+        copy_keyframes(curve, &mut visible, segment.start.max(from_tick), end);
+    }
+    visible
+}
+
+/// Copies the state of `from` at `start` and every keyframe after `start` up to and including `end` into `to`
+fn copy_keyframes<T: Clone, C: CurveTrait<T>>(from: &C, to: &mut C, start: u64, end: u64) {
+    if let Some(state) = from.get_state(start) {
+        to.insert_keyframe(start, state.to_owned());
+    }
+    let mut tick = start;
+    while let Some((next_tick, state)) = from.next_keyframe(tick) {
+        if *next_tick > end {
+            break;
+        }
+        to.insert_keyframe(*next_tick, state.clone());
+        tick = *next_tick;
+    }
+}
@@ -5,11 +5,11 @@ use bevy::{
     ecs::{
         entity::Entity,
         event::{Event, EventReader, EventWriter},
-        schedule::IntoSystemConfigs,
+        schedule::{apply_deferred, IntoSystemConfigs},
         system::{Commands, Query, Res, ResMut},
     },
 };
-use bevy_eventwork::{AppNetworkMessage, NetworkData};
+use bevy_eventwork::{AppNetworkMessage, Network, NetworkData};
 use bevy_eventwork_mod_websockets::WebSocketProvider;
 use core_library::{
     async_runners::run_async, auth_server::AccountId, authentication::AuthenticationServerInfo,
@@ -19,10 +19,15 @@ use tide::http::Url;
 
 use crate::{
     app_authentication::auth_user_request,
-    client_game_server_network::{CurrentlyConnectedPlayers, PlayerIdGameIdMapping},
+    client_game_server_network::{
+        state_sync::build_state_snapshot, ConnectionIdPlayerIdMapping, CurrentlyConnectedPlayers,
+        PlayerIdGameIdMapping,
+    },
 };
 
-use super::GameIdMapping;
+use super::{
+    load_games::LoadGameCommand, unload_games::UnloadedGames, GameIdMapping, GameInstance,
+};
 
 pub struct ClientGameConnectionPlugin;
 
@@ -37,6 +42,8 @@ impl Plugin for ClientGameConnectionPlugin {
             Update,
             (
                 handle_connecting_to_games,
+                load_connecting_players_games,
+                apply_deferred,
                 (
                     add_connected_player_to_game,
                     remove_connected_player_from_game,
@@ -122,18 +129,41 @@ async fn connecting_auth_check(
     }
 }
 
+/// Loads the games players are connecting to if they have been unloaded, so they are in memory before the player is added
+fn load_connecting_players_games(
+    mut new_messages: EventReader<AddConnectedPlayerToGameEvent>,
+    unloaded_games: Res<UnloadedGames>,
+    mut commands: Commands,
+) {
+    for message in new_messages.read() {
+        if unloaded_games.games.contains_key(&message.game_id) {
+            commands.add(LoadGameCommand {
+                game_id: message.game_id,
+            });
+        }
+    }
+}
+
+/// Adds the player to the games connected players and sends them a [`GameStateSnapshot`](core_library::network::ws_game_server::GameStateSnapshot)
+/// of the game
 fn add_connected_player_to_game(
     mut new_messages: EventReader<AddConnectedPlayerToGameEvent>,
     game_id_mapping: Res<GameIdMapping>,
     mut player_game_id_mapping: ResMut<PlayerIdGameIdMapping>,
-    mut games: Query<(Entity, Option<&mut CurrentlyConnectedPlayers>)>,
+    connection_mapping: Res<ConnectionIdPlayerIdMapping>,
+    net: Res<Network<WebSocketProvider>>,
+    mut games: Query<(
+        Entity,
+        &mut GameInstance,
+        Option<&mut CurrentlyConnectedPlayers>,
+    )>,
     mut commands: Commands,
 ) {
     for message in new_messages.read() {
         // Get the games entity
         if let Some(game_entity) = game_id_mapping.map.get(&message.game_id) {
             // Get the game components
-            if let Ok((entity, players)) = games.get_mut(*game_entity) {
+            if let Ok((entity, mut game, players)) = games.get_mut(*game_entity) {
                 if let Some(mut players) = players {
                     // insert the player into the games connected players
                     players.insert(message.player_id.clone());
@@ -148,6 +178,20 @@ fn add_connected_player_to_game(
                 player_game_id_mapping
                     .map
                     .insert(message.player_id.clone(), Some(message.game_id));
+
+                // send the player everything they can see so they can build their own copy of the game
+                if let Some((connection_id, _)) = connection_mapping
+                    .map
+                    .iter()
+                    .find(|(_, account_id)| account_id.as_ref() == Some(&message.player_id))
+                {
+                    let game_id = game.game_id;
+                    if let Some(snapshot) =
+                        build_state_snapshot(&mut game.game_world, game_id, &message.player_id)
+                    {
+                        let _ = net.send_message(*connection_id, snapshot);
+                    }
+                }
             }
         }
     }
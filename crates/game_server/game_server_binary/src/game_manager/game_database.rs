@@ -1,10 +1,22 @@
-//! Responsible for saving games and keeping them backed up into the database
+//! Responsible for saving games and keeping them backed up into the database.
+//!
+//! Games are saved in parallel. Every message a game queued while saving or simulating is held in its own buffered channels
+//! and forwarded to the server afterwards one game at a time in [`GameId`] order. Each message type is written by its own
+//! database system, so messages of the same type are written in the order they were sent but there is no ordering between
+//! messages of different types
 
 use bevy::{
     app::{Plugin, Update},
     ecs::{schedule::IntoSystemConfigs, system::Query},
 };
-use core_library::sqlite_database::{saving::SaveSchedule, DatabasePlugin};
+use core_library::{
+    game_meta::GameId,
+    sqlite_database::{
+        saving::SaveSchedule, schemes::game_server::migrations::migrate_database, Database,
+        DatabasePlugin,
+    },
+    GameWorldChannels,
+};
 
 use crate::app::app_scheduling::ServerAuthenticatedSets;
 
@@ -17,16 +29,42 @@ impl Plugin for GameDatabasePlugin {
         app.add_plugins(DatabasePlugin {
             database_path: "databases/game_server_database.db".to_string(),
         });
+        // Databases saved by older versions of the server are brought up to date before any game is created or loaded
+        let database = app.world.resource::<Database>().clone();
+        let mut connection = database
+            .connection
+            .lock()
+            .expect("Game server database unavailable");
+        migrate_database(&mut connection).expect("Failed to migrate the game server database");
 
         app.add_systems(
             Update,
-            save_games.in_set(ServerAuthenticatedSets::ServerTasks),
+            (save_games, forward_game_messages)
+                .chain()
+                .in_set(ServerAuthenticatedSets::ServerTasks),
         );
     }
 }
 
 fn save_games(mut games: Query<&mut GameInstance>) {
-    for mut game in games.iter_mut() {
+    games.par_iter_mut().for_each(|mut game| {
         game.game_world.run_schedule(SaveSchedule);
+    });
+}
+
+/// Forwards the messages every game queued in its buffered channels to the server, in [`GameId`] order
+fn forward_game_messages(games: Query<&GameInstance>) {
+    let mut games: Vec<(GameId, &GameWorldChannels)> = games
+        .iter()
+        .filter_map(|game| {
+            Some((
+                game.game_id,
+                game.game_world.get_resource::<GameWorldChannels>()?,
+            ))
+        })
+        .collect();
+    games.sort_by_key(|(game_id, _)| *game_id);
+    for (_, channels) in games {
+        channels.forward_messages();
     }
 }
@@ -0,0 +1,221 @@
+//! Responsible for loading saved games back out of the database, when the server starts and whenever an unloaded game is
+//! needed again.
+//!
+//! When the server starts every game that was in memory when it stopped or that has an event due is loaded. Every other
+//! game is recorded in [`UnloadedGames`] and only loaded by a [`LoadGameCommand`] once it is needed.
+//!
+//! Each game is rebuilt with [`create_game_world`] from its games meta row, its objects and players are spawned from its
+//! curves and players tables, and its queued actions are read back from its actions table along with what every player
+//! could see.
+//!
+//! Loaded games resume from the tick they were saved on, and the game runner then catches them up to the tick they should be
+//! on by now
+
+use bevy::{
+    app::{Plugin, Startup},
+    ecs::{
+        schedule::Schedule,
+        system::Command,
+        world::{Mut, World},
+    },
+    log::{error, info},
+};
+use core_library::{
+    game_generation::{create_game_world, StartSlots},
+    game_meta::GameId,
+    game_simulation::{
+        forecasts::forecast_actions, visibility::update_visibility, SimulationTicks,
+    },
+    sqlite_database::{
+        loading::{load_game_objects, saved_game, saved_games, SavedGame},
+        schemes::game_server::game_actions::queued_game_actions,
+        Database,
+    },
+};
+use rusqlite::Connection;
+
+use crate::game_runner::{unix_time_millis, SERVER_TICK_MILLIS};
+
+use super::{
+    unload_games::{save_wake_at, UnloadedGame, UnloadedGames},
+    update_games_meta, GameIdMapping, GameInstance, GameTickInfo,
+};
+
+pub struct LoadGamesPlugin;
+
+impl Plugin for LoadGamesPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.add_systems(Startup, load_saved_games);
+    }
+}
+
+/// Loads every saved game that has to be in memory into its own [`GameInstance`] and maps it in the [`GameIdMapping`]. The
+/// rest are recorded in the [`UnloadedGames`]
+fn load_saved_games(server_world: &mut World) {
+    let database = server_world.resource::<Database>().clone();
+    let Ok(connection) = database.connection.lock() else {
+        error!("Could not load saved games, database unavailable");
+        return;
+    };
+    let saved_games = match saved_games(&connection) {
+        Ok(saved_games) => saved_games,
+        Err(err) => {
+            error!("Could not load saved games: {}", err);
+            return;
+        }
+    };
+
+    let now = unix_time_millis();
+    for saved_game in saved_games {
+        let game_id = saved_game.game_id;
+        if saved_game.wake_at.is_none_or(|wake_at| wake_at > now) {
+            server_world.resource_mut::<UnloadedGames>().games.insert(
+                game_id,
+                UnloadedGame {
+                    wake_at: saved_game.wake_at,
+                },
+            );
+            continue;
+        }
+        if let Err(err) = load_game(server_world, &connection, saved_game) {
+            error!("Failed to load game {}: {}", game_id.id_as_string(), err);
+            continue;
+        }
+        info!("Loaded game {}", game_id.id_as_string());
+    }
+}
+
+/// Command to load an unloaded game back into memory
+pub struct LoadGameCommand {
+    /// The game to load
+    pub game_id: GameId,
+}
+
+impl Command for LoadGameCommand {
+    fn apply(self, server_world: &mut World) {
+        // Games that fail to load are forgotten until the server restarts rather than retried every frame
+        if server_world
+            .resource_mut::<UnloadedGames>()
+            .games
+            .remove(&self.game_id)
+            .is_none()
+        {
+            return;
+        }
+        let database = server_world.resource::<Database>().clone();
+        let Ok(connection) = database.connection.lock() else {
+            error!(
+                "Could not load game {}, database unavailable",
+                self.game_id.id_as_string()
+            );
+            return;
+        };
+        let saved_game = match saved_game(&connection, &self.game_id) {
+            Ok(Some(saved_game)) => saved_game,
+            Ok(None) => {
+                error!(
+                    "Tried to load game {} which does not exist",
+                    self.game_id.id_as_string()
+                );
+                return;
+            }
+            Err(err) => {
+                error!(
+                    "Failed to load game {}: {}",
+                    self.game_id.id_as_string(),
+                    err
+                );
+                return;
+            }
+        };
+        if let Err(err) = load_game(server_world, &connection, saved_game) {
+            error!(
+                "Failed to load game {}: {}",
+                self.game_id.id_as_string(),
+                err
+            );
+            return;
+        }
+        // The game is in memory again, so it has to be loaded straight away if the server restarts
+        save_wake_at(server_world, self.game_id, Some(0));
+        info!("Loaded game {}", self.game_id.id_as_string());
+    }
+}
+
+/// Rebuilds a single saved game and spawns its [`GameInstance`]
+fn load_game(
+    server_world: &mut World,
+    connection: &Connection,
+    saved_game: SavedGame,
+) -> Result<(), rusqlite::Error> {
+    let SavedGame {
+        game_id,
+        mut object_id_service,
+        seed,
+        settings,
+        game_tick,
+        started_at,
+        player_visibility,
+        start_slots,
+        ..
+    } = saved_game;
+
+    // Games saved before their start time was stored resume from the tick they were saved on
+    let started_at = started_at.unwrap_or_else(|| {
+        let started_at = unix_time_millis()
+            .saturating_sub(game_tick / settings.ticks_per_tick.max(1) * SERVER_TICK_MILLIS);
+        update_games_meta(server_world, game_id, "started_at", started_at.to_string());
+        started_at
+    });
+
+    let mut game_world = create_game_world(
+        server_world,
+        &game_id,
+        &settings,
+        &mut object_id_service,
+        seed,
+    );
+    load_game_objects(connection, &mut game_world, &game_id)?;
+    game_world.insert_resource(StartSlots {
+        outposts: start_slots,
+    });
+    let future_actions = queued_game_actions(connection, &game_id)?;
+
+    match player_visibility {
+        Some(player_visibility) => game_world.insert_resource(player_visibility),
+        // Games saved before their visibility was stored only get what players can see on the tick they were saved on
+        None => {
+            game_world.insert_resource(SimulationTicks {
+                last_simulated_tick: game_tick.saturating_sub(1),
+                current_tick: game_tick,
+            });
+            let mut visibility_schedule = Schedule::default();
+            visibility_schedule.add_systems(update_visibility);
+            visibility_schedule.run(&mut game_world);
+        }
+    }
+    game_world.insert_resource(SimulationTicks {
+        last_simulated_tick: game_tick,
+        current_tick: game_tick,
+    });
+    forecast_actions(&mut game_world, &future_actions);
+
+    let entity = server_world
+        .spawn(GameInstance {
+            game_id,
+            game_world,
+            future_actions,
+            game_tick: GameTickInfo {
+                game_tick,
+                started_at,
+                ticks_per_tick: settings.ticks_per_tick,
+                simulation_tick_amount: settings.simulation_tick_amount,
+                last_simulated_tick: game_tick,
+            },
+        })
+        .id();
+    server_world.resource_scope(|_world: &mut World, mut mapping: Mut<GameIdMapping>| {
+        mapping.map.insert(game_id, entity)
+    });
+    Ok(())
+}
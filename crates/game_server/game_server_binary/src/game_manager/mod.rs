@@ -2,6 +2,9 @@
 //! which holds all their state between the [`GameInstance`] and the stuff in the actual world.
 //!
 //! Is also responsible though `crate::game_manager::save_manager` with saving each game into the server files
+//!
+//! Only games that players are connected to or that have something happening soon are kept in memory. Every other game is
+//! unloaded by [`unload_games`] and loaded again by [`load_games`] when it is needed
 
 use bevy::{
     app::Plugin,
@@ -14,21 +17,35 @@ use bevy::{
     },
     utils::HashMap,
 };
-use core_library::{authentication::AppAuthenticationState, game_meta::GameId};
+use core_library::{
+    authentication::AppAuthenticationState,
+    game_meta::GameId,
+    sqlite_database::{
+        database_traits::{DatabaseData, PureDatabaseData},
+        update_row::UpdateRow,
+    },
+    AsyncChannelSender,
+};
 
-use crate::{http_network::start_server, player_actions::PlayerAction};
+use crate::{
+    game_runner::SERVER_TICK_MILLIS, http_network::start_server, player_actions::PlayerAction,
+};
 
 use self::{
     client_game_connection::ClientGameConnectionPlugin, game_database::GameDatabasePlugin,
-    manage_players_in_games::add_join_and_quit_request, new_game::NewGamePlugin,
-    new_game_http::NewGameHttpPlugin,
+    load_games::LoadGamesPlugin, manage_players_in_games::add_join_and_quit_request,
+    new_game::NewGamePlugin, new_game_http::NewGameHttpPlugin, start_game::StartGamePlugin,
+    unload_games::UnloadGamesPlugin,
 };
 
 pub mod client_game_connection;
 mod game_database;
+mod load_games;
 mod manage_players_in_games;
 mod new_game;
 mod new_game_http;
+mod start_game;
+pub mod unload_games;
 
 pub struct GameManagerPlugin;
 
@@ -42,6 +59,9 @@ impl Plugin for GameManagerPlugin {
             NewGameHttpPlugin,
             NewGamePlugin,
             ClientGameConnectionPlugin,
+            StartGamePlugin,
+            LoadGamesPlugin,
+            UnloadGamesPlugin,
         ));
 
         app.add_systems(
@@ -51,6 +71,24 @@ impl Plugin for GameManagerPlugin {
     }
 }
 
+/// Saves `data` into a single column of the games row in the games meta table
+fn update_games_meta(server_world: &World, game_id: GameId, column_name: &str, data: String) {
+    let Some(row_id) = game_id.to_database_data() else {
+        return;
+    };
+    let _ = server_world
+        .resource::<AsyncChannelSender<UpdateRow>>()
+        .sender_channel
+        .send(UpdateRow {
+            table_name: "games_meta".to_string(),
+            row_id,
+            database_data: vec![PureDatabaseData {
+                data,
+                column_name: column_name.to_string(),
+            }],
+        });
+}
+
 /// A resource that contains mappings from [`GameId`] -> [`Entity`]. This allows easy access to any specific game
 #[derive(Resource)]
 pub struct GameIdMapping {
@@ -72,9 +110,10 @@ pub struct GameInstance {
 pub struct GameTickInfo {
     /// The current game tick
     pub game_tick: u64,
-    /// The amount of ticks that will be ticked every time the server is ticked.
-    ///
-    /// These are ticked sequentually with the fixed time step running in full for every tick
+    /// The unix time in milliseconds that tick 0 of the game happened at. The game is always ticked up to the tick it should
+    /// have reached by now, so it never drifts from real time
+    pub started_at: u64,
+    /// The amount of ticks that pass every [`SERVER_TICK_MILLIS`](crate::game_runner::SERVER_TICK_MILLIS).
     pub ticks_per_tick: u64,
     /// The minimum amount of ticks before the game will simulate.
     ///
@@ -84,3 +123,15 @@ pub struct GameTickInfo {
     /// Holds the last tick that this game was simulated
     pub last_simulated_tick: u64,
 }
+
+impl GameTickInfo {
+    /// The tick the game should be on at the unix time `now`, in milliseconds
+    pub fn expected_tick(&self, now: u64) -> u64 {
+        now.saturating_sub(self.started_at) / SERVER_TICK_MILLIS * self.ticks_per_tick
+    }
+
+    /// The unix time in milliseconds that the game reaches `tick`
+    pub fn tick_time(&self, tick: u64) -> u64 {
+        self.started_at + tick.div_ceil(self.ticks_per_tick.max(1)) * SERVER_TICK_MILLIS
+    }
+}
@@ -11,11 +11,15 @@ use bevy::{
 };
 use core_library::{
     auth_server::AccountId,
-    game_generation::{create_game_world, insert_new_game_state},
+    game_generation::{
+        create_game_world, custom_map::CustomMap, generate_map_layout, insert_new_game_state,
+        random_seed, StartSlots,
+    },
     game_meta::GameId,
-    game_meta::NewGameSettings,
+    game_meta::{GameSeed, NewGameSettings},
     objects::ObjectIdService,
     sqlite_database::schemes::game_server::{
+        game_actions::CreateGameActionsTable,
         game_tables::{CreateGameCurvesTable, CreateGamePlayersTable},
         games_meta::InsertGamesMetaRow,
     },
@@ -24,7 +28,7 @@ use core_library::{
 
 use bevy::ecs::system::Command;
 
-use crate::app::app_scheduling::ServerAuthenticatedSets;
+use crate::{app::app_scheduling::ServerAuthenticatedSets, game_runner::unix_time_millis};
 
 use super::{new_game_http::requests::NewGameCommandsChannel, GameIdMapping, GameInstance};
 
@@ -56,9 +60,16 @@ pub fn generate_new_game(
     settings: NewGameSettings,
     new_game_id: GameId,
     id_service: &mut ObjectIdService,
+    seed: GameSeed,
+    custom_map: Option<CustomMap>,
+    started_at: u64,
 ) -> Entity {
-    let mut game_world = create_game_world(server_world, &new_game_id, &settings, id_service);
-    insert_new_game_state(&mut game_world, &new_game_id, &settings);
+    let mut game_world = create_game_world(server_world, &new_game_id, &settings, id_service, seed);
+    let layout = match custom_map {
+        Some(custom_map) => custom_map.map_layout(),
+        None => generate_map_layout(&settings, seed),
+    };
+    insert_new_game_state(&mut game_world, &new_game_id, layout);
 
     let entity = server_world
         .spawn(GameInstance {
@@ -67,6 +78,7 @@ pub fn generate_new_game(
             future_actions: vec![],
             game_tick: super::GameTickInfo {
                 game_tick: 0,
+                started_at,
                 ticks_per_tick: settings.ticks_per_tick,
                 simulation_tick_amount: settings.simulation_tick_amount,
                 last_simulated_tick: 0,
@@ -87,33 +99,38 @@ pub struct NewGameCommand {
     pub new_game_id: GameId,
     /// The player who requested to start the game
     pub owning_player: Option<AccountId>,
+    /// The validated custom map to play on, if [`NewGameSettings::custom_map`] was set
+    pub custom_map: Option<CustomMap>,
 }
 
 impl Command for NewGameCommand {
     fn apply(self, server_world: &mut bevy::prelude::World) {
         let max_players = self.new_game_settings.max_player_count;
+        let started_at = unix_time_millis();
         let mut id_service = ObjectIdService::new();
+        let seed = self
+            .new_game_settings
+            .seed
+            .map(|seed| GameSeed { seed })
+            .unwrap_or_else(random_seed);
 
         server_world.resource_scope(
-            |_world: &mut World, channel: Mut<AsyncChannelSender<InsertGamesMetaRow>>| {
-                let _ = channel.sender_channel.send(InsertGamesMetaRow {
+            |_world: &mut World, channel: Mut<AsyncChannelSender<CreateGameCurvesTable>>| {
+                let _ = channel.sender_channel.send(CreateGameCurvesTable {
                     game_id: self.new_game_id,
-                    max_players,
-                    object_id_service: id_service.clone(),
-                    owning_player: self.owning_player,
                 });
             },
         );
         server_world.resource_scope(
-            |_world: &mut World, channel: Mut<AsyncChannelSender<CreateGameCurvesTable>>| {
-                let _ = channel.sender_channel.send(CreateGameCurvesTable {
+            |_world: &mut World, channel: Mut<AsyncChannelSender<CreateGamePlayersTable>>| {
+                let _ = channel.sender_channel.send(CreateGamePlayersTable {
                     game_id: self.new_game_id,
                 });
             },
         );
         server_world.resource_scope(
-            |_world: &mut World, channel: Mut<AsyncChannelSender<CreateGamePlayersTable>>| {
-                let _ = channel.sender_channel.send(CreateGamePlayersTable {
+            |_world: &mut World, channel: Mut<AsyncChannelSender<CreateGameActionsTable>>| {
+                let _ = channel.sender_channel.send(CreateGameActionsTable {
                     game_id: self.new_game_id,
                 });
             },
@@ -121,9 +138,33 @@ impl Command for NewGameCommand {
 
         let game_id = generate_new_game(
             server_world,
-            self.new_game_settings,
+            self.new_game_settings.clone(),
             self.new_game_id,
             &mut id_service,
+            seed,
+            self.custom_map,
+            started_at,
+        );
+
+        // Start slots are only known once the map has been generated
+        let start_slots = server_world
+            .get::<GameInstance>(game_id)
+            .and_then(|game| game.game_world.get_resource::<StartSlots>())
+            .map(|start_slots| start_slots.outposts.clone())
+            .unwrap_or_default();
+        server_world.resource_scope(
+            |_world: &mut World, channel: Mut<AsyncChannelSender<InsertGamesMetaRow>>| {
+                let _ = channel.sender_channel.send(InsertGamesMetaRow {
+                    game_id: self.new_game_id,
+                    max_players,
+                    object_id_service: id_service.clone(),
+                    owning_player: self.owning_player,
+                    seed,
+                    settings: self.new_game_settings,
+                    started_at,
+                    start_slots,
+                });
+            },
         );
         server_world.resource_scope(|_world: &mut World, mut mapping: Mut<GameIdMapping>| {
             mapping.map.insert(self.new_game_id, game_id)
@@ -11,7 +11,8 @@ use core_library::{
         AccountId,
     },
     authentication::client_authentication::Claims,
-    game_meta::{GameId, NewGameSettings},
+    game_generation::custom_map::load_custom_map,
+    game_meta::{GameId, MapSize, NewGameSettings},
     http_server::request_access_token,
     network::{GameAddrInfo, HttpRequestMeta},
 };
@@ -61,9 +62,23 @@ async fn request_new_game(
     self_server_id: Uuid,
     channel: NewGameCommandsChannel,
 ) -> tide::Result {
-    let request: HttpRequestMeta<NewGameSettings> = req.body_json().await?;
+    let mut request: HttpRequestMeta<NewGameSettings> = req.body_json().await?;
     let access_token = request_access_token(&req)?;
     let body = auth_user_request(access_token.clone(), auth_server_addr.clone()).await?;
+    // Custom maps are validated before a game id is requested so that invalid maps never create a game
+    let custom_map = match &request.request.custom_map {
+        Some(map_name) => Some(
+            load_custom_map(map_name, request.request.max_player_count)
+                .map_err(|err| Error::from_str(400, err))?,
+        ),
+        None => None,
+    };
+    // Custom maps bring their own size, which replaces whatever size was requested
+    if let Some(custom_map) = &custom_map {
+        request.request.map_size = MapSize::Custom {
+            dimensions: custom_map.map_size,
+        };
+    }
     let requesting_player: Option<AccountId> = match body.text() {
         Some(text) => {
             let claims: Claims = serde_json::from_str(text).unwrap();
@@ -86,6 +101,7 @@ async fn request_new_game(
         new_game_settings: request.request,
         new_game_id,
         owning_player: requesting_player,
+        custom_map,
     });
     Ok(tide::Response::builder(200)
         .body(
@@ -0,0 +1,282 @@
+//! Responsible for starting games that are still in the pregame lobby.
+//!
+//! The owning player of a game sends a [`StartGame`] request. Once verified the game server gives every player in the game
+//! their home outpost and marks the game as started so that no one else can join. Tick 0 of a game is the moment it is
+//! started, not the moment its lobby was created
+
+use std::sync::mpsc::Sender;
+
+use bevy::{
+    app::{Plugin, Update},
+    ecs::{
+        schedule::{IntoSystemConfigs, OnEnter},
+        system::{Command, Commands, Res, ResMut},
+        world::{Mut, World},
+    },
+    log::error,
+};
+use bevy_eventwork::async_trait;
+use core_library::{
+    auth_server::AccountId,
+    authentication::{
+        client_authentication::ClientAuthenticationInfo, AppAuthenticationState,
+        AuthenticationServerInfo,
+    },
+    game_generation::allocate_players,
+    game_meta::{GameId, GamePlayers},
+    game_simulation::{event_scheduling::NextInterestingTick, SimulationTicks},
+    http_server::TideServerResource,
+    network::{game_http::StartGame, HttpRequestMeta},
+    sqlite_database::{
+        database_traits::{DatabaseData, PureDatabaseData},
+        update_row::UpdateRow,
+        Database,
+    },
+    AsyncChannel, AsyncChannelSender,
+};
+use tide::{http::Url, Endpoint, Error, Request};
+
+use crate::{
+    app::app_scheduling::ServerAuthenticatedSets, app_authentication::auth_user_request,
+    game_runner::unix_time_millis, http_network::start_server,
+};
+
+use super::{load_games::LoadGameCommand, unload_games::Unloading, GameIdMapping, GameInstance};
+
+pub struct StartGamePlugin;
+
+impl Plugin for StartGamePlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.init_resource::<AsyncChannel<StartGameCommand>>();
+        app.add_systems(
+            OnEnter(AppAuthenticationState::Authenticated),
+            add_start_game_request.before(start_server),
+        );
+        app.add_systems(
+            Update,
+            read_start_game_command_channel.in_set(ServerAuthenticatedSets::ServerTasks),
+        );
+    }
+}
+
+fn add_start_game_request(
+    mut tide: ResMut<TideServerResource>,
+    auth: Res<AuthenticationServerInfo>,
+    client: Res<ClientAuthenticationInfo>,
+    database: Res<Database>,
+    channel: Res<AsyncChannel<StartGameCommand>>,
+) {
+    tide.0.at("/games/start_game").post(StartGameEndpoint {
+        authentication_server_addr: auth.addr.clone(),
+        server_access_token: client.sign_in_info.access_token.clone(),
+        database: database.clone(),
+        start_game_channel: channel.sender_channel.clone(),
+    });
+}
+
+fn read_start_game_command_channel(
+    channel: Res<AsyncChannel<StartGameCommand>>,
+    mut commands: Commands,
+) {
+    if let Ok(receiver) = channel.reciever_channel.try_lock() {
+        while let Ok(start_game_command) = receiver.try_recv() {
+            commands.add(start_game_command);
+        }
+    }
+}
+
+/// A request to start a game
+pub struct StartGameEndpoint {
+    pub(crate) server_access_token: String,
+    pub(crate) authentication_server_addr: Url,
+    pub(crate) database: Database,
+    pub(crate) start_game_channel: Sender<StartGameCommand>,
+}
+
+#[async_trait]
+impl Endpoint<()> for StartGameEndpoint {
+    async fn call(&self, req: Request<()>) -> tide::Result {
+        start_game(
+            req,
+            self.server_access_token.clone(),
+            self.authentication_server_addr.clone(),
+            self.database.clone(),
+            self.start_game_channel.clone(),
+        )
+        .await
+    }
+}
+
+struct StartDbQuery {
+    game_players: String,
+    owning_player: Option<String>,
+    game_state: u8,
+}
+
+/// Handles requests to start a game
+///
+/// Verifies that the player owns the game and that the game is still in the lobby before it starts the game
+async fn start_game(
+    mut req: Request<()>,
+    access_token: String,
+    auth_server_addr: Url,
+    database: Database,
+    start_game_channel: Sender<StartGameCommand>,
+) -> tide::Result {
+    let request: HttpRequestMeta<StartGame> = req.body_json().await?;
+    auth_user_request(access_token.clone(), auth_server_addr.clone()).await?;
+
+    let Some(game_id) = request.request.game_id.to_database_string() else {
+        return Err(Error::from_str(500, "Invalid game_id"));
+    };
+
+    let server_info = {
+        let Ok(connection) = database.connection.lock() else {
+            return Err(Error::from_str(500, "Database unavailable"));
+        };
+        let mut stmt = connection.prepare(
+            "SELECT game_players, owning_player, game_state FROM games_meta WHERE game_id = ?1",
+        )?;
+        stmt.query_row([game_id], |row| {
+            Ok(StartDbQuery {
+                game_players: row.get(0)?,
+                owning_player: row.get(1)?,
+                game_state: row.get(2)?,
+            })
+        })?
+    };
+
+    let owning_player = server_info
+        .owning_player
+        .and_then(|player| serde_json::from_str::<AccountId>(&player).ok());
+    if owning_player.as_ref() != Some(&request.request.player_id) {
+        return Err(Error::from_str(
+            403,
+            "Only the owning player can start the game",
+        ));
+    }
+    if server_info.game_state != 0 {
+        return Err(Error::from_str(409, "Game already started"));
+    }
+
+    let game_players = match serde_json::from_str::<GamePlayers>(&server_info.game_players) {
+        Ok(info) => info,
+        Err(err) => return Err(Error::from_str(500, err)),
+    };
+    if game_players.count() == 0 {
+        return Err(Error::from_str(400, "Game has no players"));
+    }
+
+    let _ = start_game_channel.send(StartGameCommand {
+        game_id: request.request.game_id,
+        game_players,
+    });
+
+    Ok(tide::Response::builder(200).build())
+}
+
+/// Command to start a game that is in the pregame lobby
+///
+/// Gives every player their home outpost, restarts the games clock from tick 0 and marks the game as started in the database
+pub struct StartGameCommand {
+    /// The game to start
+    pub game_id: GameId,
+    /// Every player that is playing in the game
+    pub game_players: GamePlayers,
+}
+
+impl Command for StartGameCommand {
+    fn apply(self, server_world: &mut World) {
+        // Games waiting in the lobby are unloaded like any other idle game
+        LoadGameCommand {
+            game_id: self.game_id,
+        }
+        .apply(server_world);
+        let Some(game_entity) = server_world
+            .resource::<GameIdMapping>()
+            .map
+            .get(&self.game_id)
+            .copied()
+        else {
+            error!(
+                "Tried to start game {} which does not exist",
+                self.game_id.id_as_string()
+            );
+            return;
+        };
+        // Starting the game gives it events to simulate, so it is no longer idle
+        server_world.entity_mut(game_entity).remove::<Unloading>();
+        let Some(mut game) = server_world.get_mut::<GameInstance>(game_entity) else {
+            return;
+        };
+
+        // Two start requests can both pass the lobby check before either is applied. Players only exist in the game world
+        // once it has started, so only the first one starts the game
+        let game_world = &mut game.game_world;
+        if game_world
+            .query::<&AccountId>()
+            .iter(game_world)
+            .next()
+            .is_some()
+        {
+            error!(
+                "Tried to start game {} which has already started",
+                self.game_id.id_as_string()
+            );
+            return;
+        }
+
+        if let Err(err) = allocate_players(&mut game.game_world, &self.game_players) {
+            error!(
+                "Failed to start game {}: {}",
+                self.game_id.id_as_string(),
+                err
+            );
+            return;
+        }
+        // Players now own outposts that produce, so the game needs to work out its next interesting tick again
+        game.game_world
+            .insert_resource(NextInterestingTick::default());
+
+        // Nothing happens while a game waits in the lobby, so the game is anchored to the moment it starts. Otherwise it
+        // would have to catch up on every tick it spent in the lobby
+        let started_at = unix_time_millis();
+        game.game_tick.started_at = started_at;
+        game.game_tick.game_tick = 0;
+        game.game_tick.last_simulated_tick = 0;
+        game.game_world.insert_resource(SimulationTicks {
+            last_simulated_tick: 0,
+            current_tick: 0,
+        });
+
+        let Some(game_id) = self.game_id.to_database_data() else {
+            return;
+        };
+        server_world.resource_scope(
+            |_world: &mut World, channel: Mut<AsyncChannelSender<UpdateRow>>| {
+                let _ = channel.sender_channel.send(UpdateRow {
+                    table_name: "games_meta".to_string(),
+                    row_id: game_id,
+                    database_data: vec![
+                        PureDatabaseData {
+                            data: 1.to_string(),
+                            column_name: "game_state".to_string(),
+                        },
+                        PureDatabaseData {
+                            data: 0.to_string(),
+                            column_name: "has_space".to_string(),
+                        },
+                        PureDatabaseData {
+                            data: started_at.to_string(),
+                            column_name: "started_at".to_string(),
+                        },
+                        PureDatabaseData {
+                            data: 0.to_string(),
+                            column_name: "game_tick".to_string(),
+                        },
+                    ],
+                });
+            },
+        );
+    }
+}
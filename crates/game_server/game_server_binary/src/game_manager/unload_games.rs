@@ -0,0 +1,172 @@
+//! Responsible for keeping the memory the server uses bounded by unloading games that are not being played.
+//!
+//! A game is unloaded once no players are connected to it and its next army arrival, interception or queued action is more
+//! than [`UNLOAD_HORIZON_MILLIS`] away. Production is left out since it happens every few ticks in every started game and
+//! the ticks it missed are produced while the game catches up.
+//!
+//! Unloading happens in two steps. The game is first marked [`Unloading`], which stops it from being ticked while its last
+//! save is forwarded to the database. Once the database has executed every pending write the game is despawned and
+//! recorded in [`UnloadedGames`] along with the time its next event is due. That time is also saved into the games meta
+//! table so the server knows which games it has to load when it restarts.
+//!
+//! Unloaded games are loaded again with a [`LoadGameCommand`] when a player connects to them, when they are started, or
+//! when their next event comes due. The game runner then catches them up to the tick they should be on by now
+
+use bevy::{
+    app::{Plugin, PostUpdate, Update},
+    ecs::{
+        component::Component,
+        entity::Entity,
+        query::{Has, With},
+        schedule::IntoSystemConfigs,
+        system::{Commands, Query, Res, Resource},
+        world::World,
+    },
+    log::info,
+    utils::HashMap,
+};
+use core_library::{game_meta::GameId, sqlite_database::schemes::PendingSqlAction};
+
+use crate::{
+    app::app_scheduling::ServerAuthenticatedSets,
+    client_game_server_network::CurrentlyConnectedPlayers,
+    game_runner::{next_event_tick, unix_time_millis},
+};
+
+use super::{load_games::LoadGameCommand, update_games_meta, GameIdMapping, GameInstance};
+
+/// Games with an event due sooner than this many milliseconds from now are kept in memory
+pub const UNLOAD_HORIZON_MILLIS: u64 = 5 * 60 * 1000;
+
+pub struct UnloadGamesPlugin;
+
+impl Plugin for UnloadGamesPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.init_resource::<UnloadedGames>();
+        app.add_systems(
+            Update,
+            (mark_idle_games, load_due_games).in_set(ServerAuthenticatedSets::ServerTasks),
+        );
+        // Runs after every sql action read this frame has been spawned, so none are missed when checking for pending writes
+        app.add_systems(PostUpdate, unload_marked_games);
+    }
+}
+
+/// Every game that has been unloaded from memory
+#[derive(Resource, Default)]
+pub struct UnloadedGames {
+    pub games: HashMap<GameId, UnloadedGame>,
+}
+
+pub struct UnloadedGame {
+    /// The unix time in milliseconds the game has to be loaded by to simulate its next event, None if nothing is scheduled
+    pub wake_at: Option<u64>,
+}
+
+/// Marks a game that is being unloaded. Games being unloaded are not ticked, so nothing changes after their last save
+#[derive(Component)]
+pub struct Unloading {
+    /// Set once the games last save has had a full frame to reach the database
+    waited_a_frame: bool,
+}
+
+/// The unix time in milliseconds of the games next event
+fn wake_at(game: &GameInstance) -> Option<u64> {
+    next_event_tick(game).map(|tick| game.game_tick.tick_time(tick))
+}
+
+/// Whether the game can be unloaded without anyone noticing
+fn is_idle(game: &GameInstance, players: Option<&CurrentlyConnectedPlayers>, now: u64) -> bool {
+    players.is_none_or(|players| players.players.is_empty())
+        && wake_at(game).is_none_or(|wake_at| wake_at > now + UNLOAD_HORIZON_MILLIS)
+}
+
+/// Marks every idle game to be unloaded, and unmarks games being unloaded that are no longer idle
+fn mark_idle_games(
+    games: Query<(
+        Entity,
+        &GameInstance,
+        Option<&CurrentlyConnectedPlayers>,
+        Has<Unloading>,
+    )>,
+    mut commands: Commands,
+) {
+    let now = unix_time_millis();
+    for (entity, game, players, unloading) in games.iter() {
+        match (is_idle(game, players, now), unloading) {
+            (true, false) => {
+                commands.entity(entity).insert(Unloading {
+                    waited_a_frame: false,
+                });
+            }
+            (false, true) => {
+                commands.entity(entity).remove::<Unloading>();
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Despawns every game marked [`Unloading`] once the database has caught up with its last save
+fn unload_marked_games(server_world: &mut World) {
+    let now = unix_time_millis();
+    let mut marked_games = server_world.query::<(
+        Entity,
+        &GameInstance,
+        Option<&CurrentlyConnectedPlayers>,
+        &mut Unloading,
+    )>();
+    let mut ready_games = vec![];
+    for (entity, game, players, mut unloading) in marked_games.iter_mut(server_world) {
+        // A player might have connected since the game was marked
+        if !is_idle(game, players, now) {
+            continue;
+        }
+        if !unloading.waited_a_frame {
+            unloading.waited_a_frame = true;
+            continue;
+        }
+        ready_games.push((entity, game.game_id, wake_at(game)));
+    }
+    if ready_games.is_empty() {
+        return;
+    }
+
+    // Games are only unloaded once every write sent to the database has been executed, so loading them again always reads
+    // the state they were unloaded in
+    let mut pending_sql = server_world.query_filtered::<(), With<PendingSqlAction>>();
+    if pending_sql.iter(server_world).next().is_some() {
+        return;
+    }
+
+    for (entity, game_id, wake_at) in ready_games {
+        server_world.despawn(entity);
+        server_world
+            .resource_mut::<GameIdMapping>()
+            .map
+            .remove(&game_id);
+        server_world
+            .resource_mut::<UnloadedGames>()
+            .games
+            .insert(game_id, UnloadedGame { wake_at });
+        save_wake_at(server_world, game_id, wake_at);
+        info!("Unloaded game {}", game_id.id_as_string());
+    }
+}
+
+/// Loads every unloaded game whose next event has come due
+fn load_due_games(unloaded_games: Res<UnloadedGames>, mut commands: Commands) {
+    let now = unix_time_millis();
+    for (game_id, game) in unloaded_games.games.iter() {
+        if game.wake_at.is_some_and(|wake_at| wake_at <= now) {
+            commands.add(LoadGameCommand { game_id: *game_id });
+        }
+    }
+}
+
+/// Saves the time the game has to be loaded by into its row of the games meta table
+pub(super) fn save_wake_at(server_world: &World, game_id: GameId, wake_at: Option<u64>) {
+    if let Ok(wake_at) = serde_json::to_string(&wake_at) {
+        update_games_meta(server_world, game_id, "wake_at", wake_at);
+    }
+}
@@ -1,24 +1,62 @@
 //! Responsible for ticking game ticks
 //!
+//! Every game is anchored to the real time its tick 0 happened at, [`GameTickInfo::started_at`](crate::game_manager::GameTickInfo::started_at),
+//! and moves [`GameTickInfo::ticks_per_tick`](crate::game_manager::GameTickInfo::ticks_per_tick) ticks every [`SERVER_TICK_MILLIS`].
+//! Each server tick games are moved to the tick they should be on by now, so games that fell behind after a restart or a
+//! stall fast forward by simulating every tick they missed, at most [`MAX_CATCH_UP_TICKS`] every server tick.
 //!
+//! Games are only simulated once their next interesting tick is reached, see [`NextInterestingTick`], so idle games cost
+//! next to nothing. Game worlds are independent of each other, so every game is ticked in parallel on the compute task pool
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use bevy::{
     app::{FixedUpdate, Plugin},
     ecs::{
         entity::Entity,
+        query::Without,
         system::{Query, Resource, SystemState},
         world::{Mut, World},
     },
+    time::{Fixed, Time},
+};
+use core_library::{
+    game_simulation::{
+        event_scheduling::NextInterestingTick, DueActions, GameWorldSimulationSchedule,
+        SimulationTicks,
+    },
+    sqlite_database::schemes::game_server::game_actions::ExecuteGameActions,
+    AsyncChannelSender,
 };
-use core_library::game_simulation::GameWorldSimulationSchedule;
 
-use crate::game_manager::GameInstance;
+use crate::{
+    game_manager::{unload_games::Unloading, GameInstance},
+    player_actions::{update_forecasts, PlayerAction},
+};
+
+/// How long a server tick is in milliseconds
+pub const SERVER_TICK_MILLIS: u64 = 1000;
+
+/// The most ticks a single game simulates every server tick while catching up, so that one game far behind does not stall
+/// the server
+pub const MAX_CATCH_UP_TICKS: u64 = 1000;
+
+/// Returns the current unix time in milliseconds
+pub fn unix_time_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or_default()
+}
 
 pub struct GameRunnerPlugin;
 
 impl Plugin for GameRunnerPlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
-        let system_state: SystemState<Query<(Entity, &mut GameInstance)>> =
+        app.insert_resource(Time::<Fixed>::from_duration(Duration::from_millis(
+            SERVER_TICK_MILLIS,
+        )));
+        let system_state: SystemState<Query<(Entity, &mut GameInstance), Without<Unloading>>> =
             SystemState::new(&mut app.world);
         app.insert_resource(CachedSystemState {
             games_query: system_state,
@@ -29,26 +67,240 @@ impl Plugin for GameRunnerPlugin {
 
 #[derive(Resource)]
 struct CachedSystemState {
-    games_query: SystemState<Query<'static, 'static, (Entity, &'static mut GameInstance)>>,
+    games_query: SystemState<
+        Query<'static, 'static, (Entity, &'static mut GameInstance), Without<Unloading>>,
+    >,
 }
 
 fn tick_games(world: &mut World) {
+    let now = unix_time_millis();
     world.resource_scope(|world, mut query: Mut<CachedSystemState>| {
         let mut games_query = query.games_query.get_mut(world);
 
-        for (_entity, mut game) in games_query.iter_mut() {
-            game.game_tick.game_tick += 1;
-            // If the new tick - the simulation tick amount is greater than or equal to the last time the game was simulated,
-            // we need to simulate it again
-            if game
-                .game_tick
-                .game_tick
-                .saturating_sub(game.game_tick.simulation_tick_amount)
-                >= game.game_tick.last_simulated_tick
-            {
-                game.game_world.run_schedule(GameWorldSimulationSchedule);
-                game.game_tick.last_simulated_tick = game.game_tick.game_tick
-            }
-        }
+        // Every game world is independent so they are ticked in parallel. Games being unloaded are left as they were saved
+        games_query.par_iter_mut().for_each(|(_entity, mut game)| {
+            tick_game(&mut game, now);
+        });
     });
 }
+
+/// Moves the game to the tick it should be on at the unix time `now` and simulates it up to every interesting tick that
+/// has been reached
+fn tick_game(game: &mut GameInstance, now: u64) {
+    let expected_tick = game.game_tick.expected_tick(now);
+    game.game_tick.game_tick = game.game_tick.game_tick.max(expected_tick);
+    // If the new tick - the simulation tick amount is greater than or equal to the last time the game was simulated,
+    // we need to simulate it again
+    if game
+        .game_tick
+        .game_tick
+        .saturating_sub(game.game_tick.simulation_tick_amount)
+        < game.game_tick.last_simulated_tick
+    {
+        return;
+    }
+
+    let target_tick = game
+        .game_tick
+        .game_tick
+        .min(game.game_tick.last_simulated_tick + MAX_CATCH_UP_TICKS);
+    let mut executed_actions = false;
+    let mut simulated = false;
+
+    // Every interesting tick is simulated on its own so that armies arriving and actions being executed happen in tick order.
+    // Nothing happens on the ticks in between, so they are simulated in the same run as the interesting tick that ends them
+    while let Some(next_tick) = next_interesting_tick(game).filter(|tick| *tick <= target_tick) {
+        let last_simulated_tick = game.game_tick.last_simulated_tick;
+        let next_tick = next_tick.max(last_simulated_tick + 1);
+
+        // Hand every action whose tick has arrived to the game world to be executed
+        let (due_actions, future_actions): (Vec<PlayerAction>, Vec<PlayerAction>) =
+            std::mem::take(&mut game.future_actions)
+                .into_iter()
+                .partition(|action| action.tick_scheduled <= next_tick);
+        game.future_actions = future_actions;
+        executed_actions |= !due_actions.is_empty();
+        game.game_world
+            .resource_mut::<DueActions>()
+            .actions
+            .extend(due_actions);
+        game.game_world.insert_resource(SimulationTicks {
+            last_simulated_tick,
+            current_tick: next_tick,
+        });
+
+        game.game_world.run_schedule(GameWorldSimulationSchedule);
+        game.game_tick.last_simulated_tick = next_tick;
+        simulated = true;
+    }
+
+    // A game whose next interesting tick is further away than [`MAX_CATCH_UP_TICKS`] would never reach it, so it is simulated
+    // up to the end of the catch up window instead. Nothing happens on those ticks, but what players could see still changes
+    let last_simulated_tick = game.game_tick.last_simulated_tick;
+    if !simulated && target_tick == last_simulated_tick + MAX_CATCH_UP_TICKS {
+        game.game_world.insert_resource(SimulationTicks {
+            last_simulated_tick,
+            current_tick: target_tick,
+        });
+        game.game_world.run_schedule(GameWorldSimulationSchedule);
+        game.game_tick.last_simulated_tick = target_tick;
+        simulated = true;
+    }
+
+    // Queued actions are forecast against the curves, which the simulation just changed
+    if simulated {
+        update_forecasts(game);
+    }
+
+    // Sent through the game worlds buffered channel so it is forwarded in order with the rest of the games messages
+    if executed_actions {
+        let _ = game
+            .game_world
+            .resource::<AsyncChannelSender<ExecuteGameActions>>()
+            .sender_channel
+            .send(ExecuteGameActions {
+                game_id: game.game_id,
+                up_to_tick: game.game_tick.last_simulated_tick,
+            });
+    }
+}
+
+/// The earliest of the game worlds [`NextInterestingTick`] and the tick of its next queued action
+pub(crate) fn next_interesting_tick(game: &GameInstance) -> Option<u64> {
+    let world_tick = game
+        .game_world
+        .get_resource::<NextInterestingTick>()
+        .and_then(|next_tick| next_tick.tick);
+    world_tick.into_iter().chain(next_action_tick(game)).min()
+}
+
+/// The earliest of the game worlds [`NextInterestingTick::event_tick`] and the tick of its next queued action. Unlike
+/// [`next_interesting_tick`] this leaves out production, which is caught up on whenever the game is next simulated
+pub(crate) fn next_event_tick(game: &GameInstance) -> Option<u64> {
+    let world_tick = game
+        .game_world
+        .get_resource::<NextInterestingTick>()
+        .and_then(|next_tick| next_tick.event_tick);
+    world_tick.into_iter().chain(next_action_tick(game)).min()
+}
+
+/// The tick of the games next queued action
+fn next_action_tick(game: &GameInstance) -> Option<u64> {
+    game.future_actions
+        .iter()
+        .map(|action| action.tick_scheduled)
+        .min()
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{ecs::world::World, math::Vec2, utils::Uuid};
+    use bevy_state_curves::prelude::{CurveTrait, LinearCurve, SteppedCurve};
+    use core_library::{
+        auth_server::AccountId,
+        game_meta::GameId,
+        game_simulation::{
+            event_scheduling::NextInterestingTick,
+            forecasts::ActionForecasts,
+            visibility::{PlayerVisibility, VisibleSegment},
+            DueActions, FailedActions, GameWorldSimulationSchedule, SimulationTicks,
+        },
+        objects::{
+            army::{Army, ArmyUnits},
+            core_components::{ObjectGeneral, ObjectId, ObjectPosition},
+            outpost::{Outpost, OutpostType},
+            units::UnitComposition,
+            ObjectIdService,
+        },
+    };
+
+    use crate::game_manager::{GameInstance, GameTickInfo};
+
+    use super::{tick_game, SERVER_TICK_MILLIS};
+
+    #[test]
+    fn test_visibility_between_interesting_ticks() {
+        let player = AccountId {
+            id: Uuid::from_u128(1),
+        };
+        let enemy = AccountId {
+            id: Uuid::from_u128(2),
+        };
+        let mut game_world = World::new();
+        game_world.insert_resource(ObjectIdService::new());
+        game_world.init_resource::<SimulationTicks>();
+        game_world.init_resource::<DueActions>();
+        game_world.init_resource::<FailedActions>();
+        game_world.init_resource::<ActionForecasts>();
+        game_world.init_resource::<PlayerVisibility>();
+        game_world.init_resource::<NextInterestingTick>();
+        game_world.add_schedule(GameWorldSimulationSchedule::new_schedule());
+        game_world.spawn(player.clone());
+        game_world.spawn(enemy.clone());
+
+        // The player owns a depot, so the game is only simulated on production ticks
+        let mut position = SteppedCurve::new();
+        position.insert_keyframe(
+            0,
+            ObjectPosition {
+                position: Vec2::new(0.0, 0.0),
+            },
+        );
+        let mut general = SteppedCurve::new();
+        general.insert_keyframe(0, ObjectGeneral::new(player.clone()));
+        let mut outpost_type = SteppedCurve::new();
+        outpost_type.insert_keyframe(0, OutpostType::Depot);
+        game_world.spawn((Outpost, ObjectId::new(0), position, general, outpost_type));
+
+        // The enemy army moves 10 a tick from 105 away to 5 away from the depot and back again, passing through the depot's
+        // vision on ticks that are not interesting
+        let mut position = LinearCurve::new();
+        for (tick, x) in [(0, 105.0), (10, 5.0), (20, 105.0)] {
+            position.insert_keyframe(
+                tick,
+                ObjectPosition {
+                    position: Vec2::new(x, 0.0),
+                },
+            );
+        }
+        let mut general = SteppedCurve::new();
+        general.insert_keyframe(0, ObjectGeneral::new(enemy.clone()));
+        let mut units = SteppedCurve::new();
+        units.insert_keyframe(
+            0,
+            ArmyUnits {
+                units: UnitComposition {
+                    infantry: 10,
+                    cavalry: 0,
+                    artillery: 0,
+                },
+            },
+        );
+        game_world.spawn((Army, ObjectId::new(1), position, general, units));
+
+        let mut game = GameInstance {
+            game_id: GameId {
+                id: Uuid::from_u128(3),
+            },
+            game_world,
+            future_actions: vec![],
+            game_tick: GameTickInfo {
+                game_tick: 0,
+                started_at: 0,
+                ticks_per_tick: 1,
+                simulation_tick_amount: 1,
+                last_simulated_tick: 0,
+            },
+        };
+        for server_tick in 1..=20 {
+            tick_game(&mut game, server_tick * SERVER_TICK_MILLIS);
+        }
+
+        assert_eq!(game.game_tick.last_simulated_tick, 20);
+        let visibility = game.game_world.resource::<PlayerVisibility>();
+        assert_eq!(
+            visibility.players[&player].segments(&ObjectId::new(1)),
+            &[VisibleSegment { start: 6, end: 14 }]
+        );
+    }
+}
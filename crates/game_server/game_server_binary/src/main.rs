@@ -1,9 +1,5 @@
 use arts_server::ServerPlugin;
-use bevy::{
-    app::App,
-    time::{Fixed, Time},
-    MinimalPlugins,
-};
+use bevy::{app::App, MinimalPlugins};
 use clap::Parser;
 use console_parser::ConsoleParserPlugin;
 use core_library::{http_server::TideServerResource, network::GameAddrInfo};
@@ -30,7 +26,6 @@ fn main() {
     let mut app = App::new();
     app.insert_resource(server_connect_info);
     app.insert_resource(TideServerResource::new(http_server_addr));
-    app.insert_resource(Time::<Fixed>::from_seconds(1.0));
     app.add_plugins((MinimalPlugins, bevy::log::LogPlugin::default()));
     app.add_plugins(ServerPlugin);
     app.add_plugins(ConsoleParserPlugin);
@@ -1,10 +1,92 @@
 //! Responsible for simulating and handling Actions sent by players.
+//!
+//! Queued actions only change the curves once their tick is simulated. Until then the game world holds their
+//! [`ActionForecasts`](core_library::game_simulation::forecasts::ActionForecasts), which are recomputed on every change to the queue since cancelling or editing an action changes what
+//! the actions after it can do. Every change to the queue is also saved into the games actions table
 
-use core_library::{actions::Action, auth_server::AccountId};
+use core_library::{
+    actions::ActionId,
+    game_simulation::forecasts::forecast_actions,
+    sqlite_database::schemes::game_server::game_actions::{
+        CancelGameAction, EditGameAction, InsertGameActionsRow,
+    },
+    AsyncChannelSender,
+};
 
-/// A player action, is stored by the server and simulated ahead of time but executed only when the tick arrives.
-pub struct PlayerAction {
-    pub tick_scheduled: u64,
-    pub issued_by_player: AccountId,
-    pub action: Action,
+use crate::game_manager::GameInstance;
+
+pub use core_library::actions::PlayerAction;
+
+/// Adds the action to the games future actions and saves it into the games actions table so it survives a restart
+pub fn queue_player_action(
+    game: &mut GameInstance,
+    player_action: PlayerAction,
+    insert_action_channel: &AsyncChannelSender<InsertGameActionsRow>,
+) {
+    let _ = insert_action_channel
+        .sender_channel
+        .send(InsertGameActionsRow {
+            game_id: game.game_id,
+            player_action: player_action.clone(),
+        });
+    game.future_actions.push(player_action);
+    update_forecasts(game);
+}
+
+/// Returns the queued action with the given id, if it has not been executed yet
+pub fn queued_player_action<'a>(
+    game: &'a GameInstance,
+    action_id: &ActionId,
+) -> Option<&'a PlayerAction> {
+    game.future_actions
+        .iter()
+        .find(|action| action.action_id == *action_id)
+}
+
+/// Removes the queued action from the games future actions and marks it as cancelled in the games actions table. Returns
+/// the cancelled action, or None if it was not queued
+pub fn cancel_player_action(
+    game: &mut GameInstance,
+    action_id: &ActionId,
+    cancel_action_channel: &AsyncChannelSender<CancelGameAction>,
+) -> Option<PlayerAction> {
+    let index = game
+        .future_actions
+        .iter()
+        .position(|action| action.action_id == *action_id)?;
+    let _ = cancel_action_channel.sender_channel.send(CancelGameAction {
+        game_id: game.game_id,
+        action_id: *action_id,
+    });
+    let player_action = game.future_actions.remove(index);
+    update_forecasts(game);
+    Some(player_action)
+}
+
+/// Replaces the queued action with the same id as `player_action`. Returns false if it was not queued
+pub fn edit_player_action(
+    game: &mut GameInstance,
+    player_action: PlayerAction,
+    edit_action_channel: &AsyncChannelSender<EditGameAction>,
+) -> bool {
+    let game_id = game.game_id;
+    let Some(queued_action) = game
+        .future_actions
+        .iter_mut()
+        .find(|action| action.action_id == player_action.action_id)
+    else {
+        return false;
+    };
+    let _ = edit_action_channel.sender_channel.send(EditGameAction {
+        game_id,
+        player_action: player_action.clone(),
+    });
+    *queued_action = player_action;
+    update_forecasts(game);
+    true
+}
+
+/// Forecasts every queued action of the game again
+pub fn update_forecasts(game: &mut GameInstance) {
+    forecast_actions(&mut game.game_world, &game.future_actions);
 }